@@ -0,0 +1,127 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum SeaboxError {
+    /// The config file or merged configuration is invalid
+    Config(String),
+    /// A command line or config value is malformed
+    InvalidArgument(String),
+    /// No image was given and no default image is configured
+    NoImage,
+    /// The image couldn't be found or pulled
+    ImageNotFound(String),
+    ContainerExists(String),
+    ContainerNotFound {
+        name: String,
+        detail: String,
+        /// Names of existing boxes close to the name, closest first
+        suggestions: Vec<String>,
+    },
+    /// The engine (or the sudo command used to invoke it) couldn't be found
+    EngineMissing(String),
+    /// The engine couldn't be spawned for another reason
+    Spawn {
+        program: String,
+        source: std::io::Error,
+    },
+    /// The engine ran, but reported failure
+    CommandFailed(String),
+    /// The kernel or filesystem doesn't support idmapped mounts
+    IdmapUnsupported(String),
+    /// The script of a temp container exited with the given code
+    ScriptFailed(i32),
+    /// A session that couldn't replace the seabox process exited with the given code
+    SessionFailed(i32),
+    /// Metadata seabox keeps about boxes couldn't be read or written
+    Metadata(String),
+    /// Output of the engine couldn't be parsed
+    Parse(String),
+    /// seabox was run inside a seabox container without --force
+    NestedBox,
+}
+
+impl SeaboxError {
+    /// Process exit code to report this error with
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SeaboxError::Config(_) | SeaboxError::InvalidArgument(_) | SeaboxError::NoImage => 2,
+            SeaboxError::EngineMissing(_) => 127,
+            SeaboxError::ScriptFailed(code) | SeaboxError::SessionFailed(code) => *code,
+            _ => 1,
+        }
+    }
+
+    pub(crate) fn spawn(program: &str, source: std::io::Error) -> SeaboxError {
+        if source.kind() == std::io::ErrorKind::NotFound {
+            SeaboxError::EngineMissing(program.to_string())
+        } else {
+            SeaboxError::Spawn {
+                program: program.to_string(),
+                source,
+            }
+        }
+    }
+}
+
+impl fmt::Display for SeaboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeaboxError::Config(msg) => write!(f, "{msg}"),
+            SeaboxError::InvalidArgument(msg) => write!(f, "{msg}"),
+            SeaboxError::NoImage => write!(
+                f,
+                "No default image found and no image provided with --image"
+            ),
+            SeaboxError::ImageNotFound(image) => write!(f, "Failed to pull image '{image}'"),
+            SeaboxError::ContainerExists(name) => {
+                write!(f, "A container with name '{name}' already exists")
+            }
+            SeaboxError::ContainerNotFound {
+                name,
+                detail,
+                suggestions,
+            } => {
+                write!(f, "A container with name '{name}' does not exist")?;
+                if !suggestions.is_empty() {
+                    let suggestions: Vec<String> =
+                        suggestions.iter().map(|x| format!("'{x}'")).collect();
+                    write!(f, "\nDid you mean {}?", suggestions.join(" or "))?;
+                }
+                if !detail.is_empty() {
+                    write!(f, "\n{detail}")?;
+                }
+                Ok(())
+            }
+            SeaboxError::EngineMissing(program) => {
+                write!(f, "Failed to run '{program}': command not found")
+            }
+            SeaboxError::Spawn { program, source } => {
+                write!(f, "Failed to run '{program}': {source}")
+            }
+            SeaboxError::CommandFailed(msg) => write!(f, "{msg}"),
+            SeaboxError::IdmapUnsupported(detail) => write!(
+                f,
+                "Failed to create container: idmapped mounts are not supported by this kernel or filesystem\n{detail}\n\
+                Retry with --no-idmap to mount without idmapping (mounted files keep their host ownership), \
+                or with --auto-fallback to do so automatically when this happens"
+            ),
+            SeaboxError::ScriptFailed(code) => write!(f, "Script exited with code {code}"),
+            SeaboxError::SessionFailed(code) => write!(f, "Session exited with code {code}"),
+            SeaboxError::Metadata(msg) => write!(f, "{msg}"),
+            SeaboxError::Parse(msg) => write!(f, "{msg}"),
+            SeaboxError::NestedBox => write!(
+                f,
+                "Already inside a seabox container (SEABOX_IN_BOX is set) - pass --force to run anyway"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SeaboxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SeaboxError::Spawn { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -8,9 +8,20 @@ PARAM_PASSWORDLESS_SUDO="INSERT_PASSWORDLESS_SUDO"
 PARAM_NO_PASSWORD="INSERT_CREATE_PASSWORD"
 PARAM_VERBOSE="INSERT_VERBOSE"
 PARAM_SHELL="INSERT_SHELL"
+# Exit after setting up the user instead of starting a login shell
+PARAM_SETUP_ONLY="INSERT_SETUP_ONLY"
+# Script to run as the user instead of a login shell, passed as the first argument
+PARAM_SCRIPT="$1"
 
 SHELL="$PARAM_SHELL"
 
+# Mark the container for seabox run inside it, login shells reset the environment so also set it from the profile
+export SEABOX_IN_BOX=1
+if [ -d /etc/profile.d ];
+then
+    echo "export SEABOX_IN_BOX=1" >/etc/profile.d/seabox.sh
+fi
+
 if [ -z "$SHELL" ];
 then
     SHELL=/bin/bash
@@ -27,8 +38,8 @@ verbose_echo () {
     fi
 }
 
-# Create user
-if [ -n "$PARAM_CREATE_USER" ];
+# Create user, unless podman already added a passwd entry for it (--passwd)
+if [ -n "$PARAM_CREATE_USER" ] && ! awk -F: -v uid="$PARAM_USER_ID" '$3 == uid {found=1} END {exit !found}' /etc/passwd;
 then
     if command -v useradd >/dev/null 2>&1;
     then
@@ -165,12 +176,50 @@ then
     fi
 fi
 
+if [ -n "$PARAM_SETUP_ONLY" ];
+then
+    exit 0
+fi
+
+# Run the script as the user, propagating its exit code
+if [ -n "$PARAM_SCRIPT" ];
+then
+    if command -v su >/dev/null 2>&1;
+    then
+        exec su -s /bin/sh "$USERNAME" -c "$PARAM_SCRIPT"
+    elif command -v sudo >/dev/null 2>&1;
+    then
+        exec sudo -u "$USERNAME" -H /bin/sh -c "$PARAM_SCRIPT"
+    else
+        echo "sudo / su not installed in the container, cannot run the script as '$USERNAME'"
+        exit 1
+    fi
+fi
+
+# Source the rc file mounted with --shell-rcfile, login shells would skip it
+RCFILE=/run/seabox/rcfile
+if [ -r "$RCFILE" ];
+then
+    case "$SHELL" in
+        *bash) RC_COMMAND="exec $SHELL --rcfile $RCFILE -i" ;;
+        *) RC_COMMAND="ENV=$RCFILE exec $SHELL -i" ;;
+    esac
+fi
+
 # su to user
 if command -v su >/dev/null 2>&1;
 then
+    if [ -n "$RC_COMMAND" ];
+    then
+        exec su -s "$SHELL" - "$USERNAME" -c "$RC_COMMAND"
+    fi
     exec su -s "$SHELL" - "$USERNAME"
 elif  command -v sudo >/dev/null 2>&1;
 then
+    if [ -n "$RC_COMMAND" ];
+    then
+        exec sudo -iu "$USERNAME" /bin/sh -c "$RC_COMMAND"
+    fi
     exec sudo -iu "$USERNAME"
 else
     echo "sudo / su not installed in the container. Manually enter the container with 'distrobox enter'"
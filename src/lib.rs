@@ -0,0 +1,6577 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use figment::Figment;
+use figment::providers::Env;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{IsTerminal, Read};
+use std::process::{Command, ExitStatus, Output, Stdio};
+
+mod error;
+mod progress;
+mod runner;
+
+pub use error::SeaboxError;
+pub use runner::{ProcessRunner, Runner};
+
+use progress::Progress;
+
+pub type Result<T> = std::result::Result<T, SeaboxError>;
+
+const SEABOX_NAME: &str = "seabox";
+
+const NEW_USER_USERNAME: &str = "user";
+
+const RESOLVED_UID_LABEL: &str = "SEABOX_RESOLVED_UID";
+const RESOLVED_GID_LABEL: &str = "SEABOX_RESOLVED_GID";
+
+/// Container path the primary host directory is mounted to
+const MOUNT_TARGET_LABEL: &str = "SEABOX_MOUNT_TARGET";
+
+const DEFAULT_MOUNT_TARGET: &str = "/mount";
+/// Tag prefix of images built with --build
+const BUILD_IMAGE_PREFIX: &str = "localhost/seabox-build-";
+/// Uid and gid of the container user when the image has no user to match
+const DEFAULT_USER_ID: i64 = 1000;
+/// podman's grace period of stopped containers, in seconds
+const DEFAULT_STOP_TIMEOUT: u64 = 10;
+
+/// Label of cache volumes created by seabox
+const CACHE_LABEL: &str = "SEABOX_CACHE";
+
+/// Semicolon separated volume specs appended to the volumes of new containers
+const VOLUMES_ENV: &str = "SEABOX_VOLUMES";
+
+/// Set in seabox containers, to notice seabox being run inside of one
+const IN_BOX_ENV: &str = "SEABOX_IN_BOX";
+
+/// Set to 1 to trace engine commands, like --trace
+const TRACE_ENV: &str = "SEABOX_TRACE";
+
+/// Where the --shell-rcfile is mounted, also used by DEFAULT_SHELL and init.sh
+const SHELL_RCFILE_PATH: &str = "/run/seabox/rcfile";
+
+const DEFAULT_SUDO_PATH: &str = "sudo";
+
+fn get_default_sudo_path() -> String {
+    DEFAULT_SUDO_PATH.to_string()
+}
+
+const DEFAULT_START_TIMEOUT: u64 = 10;
+
+fn get_default_start_timeout() -> u64 {
+    DEFAULT_START_TIMEOUT
+}
+
+static DEFAULT_SHELL: &[&str] = &[
+    "/bin/sh",
+    "-c",
+    r###"USER=$(id -un 2>/dev/null)
+SHELL_PATH=""
+
+# Plain POSIX parsing, minimal images may not ship awk
+if [ -n "$USER" ] && [ -r /etc/passwd ]; then
+    while IFS=: read -r name _ _ _ _ _ shell; do
+        if [ "$name" = "$USER" ]; then
+            SHELL_PATH="$shell"
+            break
+        fi
+    done < /etc/passwd
+fi
+
+if [ -z "$SHELL_PATH" ] || [ ! -x "$SHELL_PATH" ]; then
+    if command -v /bin/bash >/dev/null 2>&1; then
+        SHELL_PATH="/bin/bash"
+    else
+        SHELL_PATH="/bin/sh"
+    fi
+fi
+
+export SHELL="$SHELL_PATH"
+
+# Source the rc file mounted with --shell-rcfile
+if [ -r /run/seabox/rcfile ]; then
+    case "$SHELL_PATH" in
+        *bash) exec "$SHELL_PATH" --rcfile /run/seabox/rcfile ;;
+        *) export ENV=/run/seabox/rcfile ;;
+    esac
+fi
+
+exec "$SHELL_PATH""###,
+];
+
+const INIT_SCRIPT: &str = include_str!("init.sh");
+
+/// Commented config file written by `config init`
+const STARTER_CONFIG: &str = include_str!("starter.toml");
+
+/// Placeholders a custom init script must contain, the user setup doesn't work without them
+const INIT_SCRIPT_REQUIRED_PLACEHOLDERS: &[&str] = &[
+    "INSERT_CREATE_USER",
+    "INSERT_NEW_USERNAME",
+    "INSERT_CONTAINER_ID",
+    "INSERT_SETUP_ONLY",
+];
+
+pub struct Context {
+    pub config: Config,
+    pub parsed_config_file: ConfigFileFormat,
+    runner: Box<dyn Runner>,
+    /// Where metadata about boxes is stored
+    data_dir: std::path::PathBuf,
+    /// Environment variables set on every engine process
+    engine_env: Vec<(String, String)>,
+    /// Don't show progress messages
+    quiet: bool,
+    /// How dry runs print commands
+    dry_run_output: DryRunOutput,
+    /// Print every engine command to stderr before running it, and its exit status after
+    trace: bool,
+    /// Whether the warning about privileged boxes was already shown by this invocation
+    privileged_warned: bool,
+}
+
+/// SELinux relabeling of a bind mount
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    ValueEnum,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Relabel {
+    /// Label the content so all containers can access it, like :z
+    Shared,
+    /// Label the content so only this container can access it, like :Z
+    Private,
+}
+
+impl Relabel {
+    /// Option to append to a --mount spec
+    fn mount_option(&self) -> &'static str {
+        match self {
+            Relabel::Shared => ",relabel=shared",
+            Relabel::Private => ",relabel=private",
+        }
+    }
+}
+
+/// How `${VAR}` references to unset environment variables in config values are handled
+#[derive(
+    Clone,
+    Copy,
+    Default,
+    Debug,
+    PartialEq,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsetVariables {
+    /// Fail with an error naming the variable
+    #[default]
+    Error,
+    /// Replace the reference with an empty string
+    Empty,
+}
+
+#[derive(Clone, Default, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Config {
+    pub image: Option<String>,
+
+    #[serde(default = "get_default_sudo_path")]
+    pub sudo_command: String,
+
+    #[serde(default)]
+    pub directory: Option<String>,
+
+    #[serde(default)]
+    pub root: bool,
+
+    #[serde(default)]
+    pub volume: Vec<String>,
+
+    #[serde(default)]
+    pub pass_through: Option<String>,
+
+    #[serde(default)]
+    pub pass_through_end: Option<String>,
+
+    #[serde(default)]
+    pub install_sudo: Option<bool>,
+
+    #[serde(default)]
+    pub no_password: bool,
+
+    #[serde(default)]
+    pub unsafe_setup_passwordless_sudo: bool,
+
+    #[serde(default)]
+    pub pull: bool,
+
+    #[serde(default)]
+    pub passwd: bool,
+
+    #[serde(default)]
+    pub no_hosts: bool,
+
+    #[serde(default)]
+    pub hosts_file: Option<String>,
+
+    #[serde(default)]
+    pub hosts: Vec<String>,
+
+    #[serde(default)]
+    pub image_fallbacks: Vec<String>,
+
+    #[serde(default)]
+    pub allowed_image_prefixes: Vec<String>,
+
+    #[serde(default)]
+    pub security_opt: Vec<String>,
+
+    #[serde(default)]
+    pub secret: Vec<String>,
+
+    #[serde(default)]
+    pub no_new_privileges: bool,
+
+    #[serde(default)]
+    pub network: Option<String>,
+
+    #[serde(default)]
+    pub network_alias: Vec<String>,
+
+    #[serde(default)]
+    pub hostname: Option<String>,
+
+    #[serde(default)]
+    pub mount_into_home: bool,
+
+    #[serde(default)]
+    pub mount_at_host_path: bool,
+
+    #[serde(default)]
+    pub no_idmap: bool,
+
+    #[serde(default)]
+    pub auto_fallback: bool,
+
+    #[serde(default)]
+    pub entrypoint: Option<String>,
+
+    #[serde(default)]
+    pub engine_env: Vec<String>,
+
+    #[serde(default)]
+    pub ulimit: Vec<String>,
+
+    #[serde(default)]
+    pub init: bool,
+
+    #[serde(default)]
+    pub mount_cache: Vec<String>,
+
+    #[serde(default)]
+    pub remove_volumes: bool,
+
+    #[serde(default)]
+    pub rootless: bool,
+
+    #[serde(default)]
+    pub label: Vec<String>,
+
+    #[serde(default)]
+    pub label_file: Option<String>,
+
+    #[serde(default)]
+    pub stop_signal: Option<String>,
+
+    #[serde(default)]
+    pub stop_timeout: Option<u64>,
+
+    #[serde(default)]
+    pub runtime: Option<String>,
+
+    #[serde(default)]
+    pub log_driver: Option<String>,
+
+    #[serde(default)]
+    pub log_opt: Vec<String>,
+
+    #[serde(default)]
+    pub mount: Vec<String>,
+
+    #[serde(default)]
+    pub privileged: Option<bool>,
+
+    #[serde(default)]
+    pub interpolate_unset: UnsetVariables,
+
+    #[serde(default)]
+    pub relabel: Option<Relabel>,
+
+    #[serde(default)]
+    pub auto_relabel: bool,
+
+    #[serde(default)]
+    pub detach_keys: Option<String>,
+
+    #[serde(default)]
+    pub group_add: Vec<String>,
+
+    #[serde(default)]
+    pub host_uid: Option<u32>,
+
+    #[serde(default)]
+    pub host_gid: Option<u32>,
+
+    #[serde(default)]
+    pub memory: Option<String>,
+
+    #[serde(default)]
+    pub memory_swap: Option<String>,
+
+    #[serde(default)]
+    pub memory_swappiness: Option<u8>,
+
+    #[serde(default)]
+    pub cpus: Option<String>,
+
+    #[serde(default)]
+    pub cpuset_cpus: Option<String>,
+
+    #[serde(default)]
+    pub cpu_shares: Option<u64>,
+
+    #[serde(default)]
+    pub shell_rcfile: Option<String>,
+
+    #[serde(default)]
+    pub init_script: Option<String>,
+
+    #[serde(default)]
+    pub tz: Option<String>,
+
+    #[serde(default)]
+    pub copy_resolv_conf: bool,
+
+    #[serde(default)]
+    pub resolv_conf: Option<String>,
+
+    #[serde(default)]
+    pub pid: Option<String>,
+
+    #[serde(default = "get_default_start_timeout")]
+    pub start_timeout: u64,
+
+    #[serde(default)]
+    pub suppress_privileged_warning: bool,
+
+    #[serde(default)]
+    pub workdir: Option<String>,
+
+    #[serde(default)]
+    pub temp_command: Vec<String>,
+}
+
+/// The seabox config file, seabox.toml.
+///
+/// Top-level keys set defaults for new containers. Tables under `presets` are applied with
+/// --preset NAME, and any other table is a profile applied to containers of the image it is
+/// named after. Every top-level key can also be set with a SEABOX_<KEY> environment variable.
+#[derive(Default, Debug, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
+#[schemars(title = "seabox config file")]
+pub struct ConfigFileFormat {
+    #[serde(flatten)]
+    pub base: BaseConfig,
+
+    /// Named sets of settings, applied with --preset NAME
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub presets: HashMap<String, BaseConfig>,
+
+    /// Settings for containers of a specific image, keyed by image name
+    #[serde(flatten)]
+    pub image_specific: HashMap<String, BaseConfig>,
+}
+
+/// Settings for new containers. Command line flags of the same name take precedence.
+#[derive(Default, Debug, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
+pub struct BaseConfig {
+    /// Image to create containers from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Host directory to mount into the container, nothing is mounted when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// Use the root user in the container and skip creating a matching user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<bool>,
+    /// Additional mounts, as "host_path:container_path" or a single path mounted to the same path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<Vec<String>>,
+    /// Additional arguments to pass to podman, before the generated flags. Environment
+    /// variables are expanded here too, a literal $ is written $$
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pass_through: Option<String>,
+    /// Additional arguments to pass to podman, after the generated flags. Environment
+    /// variables are expanded here too, a literal $ is written $$
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pass_through_end: Option<String>,
+    /// Command used to invoke podman as root, defaults to sudo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sudo_command: Option<String>,
+    /// Install sudo in the container if it is missing. Prompts when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub install_sudo: Option<bool>,
+    /// Don't prompt to set a password for the container user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_password: Option<bool>,
+    /// Give the container user passwordless sudo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsafe_setup_passwordless_sudo: Option<bool>,
+    /// Always pull the image before creating a container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull: Option<bool>,
+    /// Let podman add a /etc/passwd entry for the container user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passwd: Option<bool>,
+    /// Don't let podman manage /etc/hosts in the container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_hosts: Option<bool>,
+    /// Host file to mount read-only as /etc/hosts in the container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hosts_file: Option<String>,
+    /// Extra /etc/hosts entries of containers, as "name:ip"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hosts: Option<Vec<String>>,
+    /// Images to try when the image isn't available locally
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_fallbacks: Option<Vec<String>>,
+    /// When set, only images starting with one of these prefixes can be used, like "registry.example.com/"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_image_prefixes: Option<Vec<String>>,
+    /// Security options passed to podman with --security-opt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security_opt: Option<Vec<String>>,
+    /// Podman secrets made available to containers, as "NAME" or "NAME,type=mount|env,..."
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<Vec<String>>,
+    /// Prevent processes in the container from gaining additional privileges
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_new_privileges: Option<bool>,
+    /// Network mode of the container, defaults to host
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Network-scoped aliases of the container, ignored with host networking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network_alias: Option<Vec<String>>,
+    /// Hostname of the container, defaults to seabox-<name>
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Mount the host directory into the home directory of the container user instead of /mount
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mount_into_home: Option<bool>,
+    /// Mount the host directory to the same absolute path in the container instead of /mount
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mount_at_host_path: Option<bool>,
+    /// Mount host directories without idmapping
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_idmap: Option<bool>,
+    /// Retry without idmapping if idmapped mounts are not supported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_fallback: Option<bool>,
+    /// Override the entrypoint of the image, an empty string resets it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<String>,
+    /// Environment variables for the podman process, as "KEY=VALUE"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine_env: Option<Vec<String>>,
+    /// Resource limits passed to podman with --ulimit, as "name=soft:hard" or "name=value"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ulimit: Option<Vec<String>>,
+    /// Run a minimal init process as PID 1 that reaps zombie processes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init: Option<bool>,
+    /// Named cache volumes shared between containers, as "name:container_path"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mount_cache: Option<Vec<String>>,
+    /// Also remove anonymous volumes of containers when removing them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove_volumes: Option<bool>,
+    /// Run podman rootless as the current user instead of through the sudo command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rootless: Option<bool>,
+    /// Labels added to containers, as "key=value"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<Vec<String>>,
+    /// File of "key=value" labels added to containers, one per line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_file: Option<String>,
+    /// Signal sent to containers when they are stopped, as a name such as "SIGINT" or a number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_signal: Option<String>,
+    /// Seconds containers get to shut down after the stop signal before they are killed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_timeout: Option<u64>,
+    /// OCI runtime containers are run with, such as "crun", "runc" or "youki"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<String>,
+    /// Log driver of containers, such as "journald" or "k8s-file"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_driver: Option<String>,
+    /// Options of the log driver, as "key=value", such as "max-size=10m"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_opt: Option<Vec<String>>,
+    /// Mounts in podman's --mount syntax, such as "type=bind,source=/srv,destination=/srv,ro=true"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mount: Option<Vec<String>>,
+    /// Run containers with podman --privileged. Defaults to true for create and false for temp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+    /// How environment variables in config values that aren't set are handled, "error" (default) or "empty"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interpolate_unset: Option<UnsetVariables>,
+    /// Image profile an image profile inherits settings from, which its own settings override
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// SELinux relabeling of the host directory mounted with -d, "shared" or "private"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relabel: Option<Relabel>,
+    /// Relabel the host directory mounted with -d as private when SELinux is enforcing and relabel isn't set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_relabel: Option<bool>,
+    /// Key sequence for detaching from containers, such as "ctrl-x,ctrl-y"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detach_keys: Option<String>,
+    /// Supplementary groups of processes in containers, by name or numeric gid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_add: Option<Vec<String>>,
+    /// Host uid that idmapped mounts map the container user to, defaults to the uid running seabox
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_uid: Option<u32>,
+    /// Host gid that idmapped mounts map the container user's group to, defaults to the gid running seabox
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_gid: Option<u32>,
+    /// Memory limit of containers, like "4g"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+    /// Limit of memory plus swap of containers, at least memory, or "-1" for unlimited swap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_swap: Option<String>,
+    /// How readily the kernel swaps out memory of containers, from 0 to 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_swappiness: Option<u8>,
+    /// Number of CPUs containers can use, like "2" or "1.5"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<String>,
+    /// CPUs containers are pinned to, as a list like "0-3" or "0,2,4"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpuset_cpus: Option<String>,
+    /// Relative CPU weight of containers when CPUs are contended, 1024 by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_shares: Option<u64>,
+    /// Host rc file mounted into containers for default shells to source
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell_rcfile: Option<String>,
+    /// Host file replacing the built-in script that sets up the container user, with the same INSERT_* placeholders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub init_script: Option<String>,
+    /// Timezone of containers, like "Europe/Berlin", or "host" to use the host's /etc/localtime
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tz: Option<String>,
+    /// Mount the host's /etc/resolv.conf into containers that don't use host networking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy_resolv_conf: Option<bool>,
+    /// Host file mounted as /etc/resolv.conf in containers that don't use host networking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolv_conf: Option<String>,
+    /// PID namespace of containers, like "container:NAME" to share another box's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<String>,
+    /// Seconds enter waits for a stopped container it starts to be running, defaults to 10
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_timeout: Option<u64>,
+    /// Don't warn when creating a privileged container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suppress_privileged_warning: Option<bool>,
+    /// Working directory of containers, defaults to the -d mount or the first --volume
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workdir: Option<String>,
+    /// Command temp containers run instead of an interactive shell, unless --shell or --script is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_command: Option<Vec<String>>,
+}
+
+#[derive(Parser)]
+#[command(version, about, long_about=None, arg_required_else_help = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "KEY=VALUE",
+        help = "Set an environment variable for the engine process",
+        long_help = "Set an environment variable for the podman process itself rather than the container, such as CONTAINERS_STORAGE_CONF or XDG_RUNTIME_DIR. Can be specified multiple times"
+    )]
+    pub engine_env: Vec<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Print every engine command to stderr as it runs, with its exit status",
+        long_help = "Print every engine command to stderr just before running it, and its exit status afterward. Unlike --dry-run the commands are run. Can also be set with SEABOX_TRACE=1"
+    )]
+    pub trace: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    Create(CreateArgs),
+    Enter(EnterArgs),
+    #[clap(visible_alias("rm"))]
+    Remove(RemoveArgs),
+    #[clap(visible_alias("tmp"))]
+    Temp(TempArgs),
+    #[clap(visible_alias("ls"))]
+    List(ListArgs),
+    Restart(RestartArgs),
+    Stop(StopArgs),
+    #[command(
+        about = "Create the boxes defined in a file",
+        long_about = "Create every box defined in a TOML file, in order, without entering them. Each [[box]] table has a name and the keys of the config file, such as image and directory. Boxes that already exist are left as they are.\nExample: seabox up boxes.toml"
+    )]
+    Up(UpArgs),
+    #[command(
+        about = "Remove the boxes defined in a file",
+        long_about = "Remove every box defined in a TOML file for 'seabox up', in reverse order. Boxes that don't exist are skipped."
+    )]
+    Down(DownArgs),
+    #[command(
+        about = "Recreate a container on the latest version of its image",
+        long_about = "Pull the latest version of the container's image and recreate the container with the same name and mounts. WARNING: the writable layer of the container is discarded - changes made outside of mounted directories are lost."
+    )]
+    Update(UpdateArgs),
+    #[command(
+        about = "Recreate a container exactly as it was created",
+        long_about = "Remove a container and create it again from the configuration and arguments recorded when it was created, even if the config file has changed since. WARNING: the writable layer of the container is discarded - changes made outside of mounted directories are lost."
+    )]
+    Recreate(RecreateArgs),
+    #[command(
+        about = "Print a field of a container, for use in scripts",
+        long_about = "Print a single field of a container without decoration, for use in scripts.\nExample: cd $(seabox which dev --field mount)"
+    )]
+    Which(WhichArgs),
+    #[command(about = "Remove data seabox created that is no longer needed")]
+    Prune(PruneArgs),
+    #[command(
+        about = "Check that the host is ready to run seabox containers",
+        long_about = "Check that the engine runs, that the kernel supports idmapped mounts and for other common setup problems. Exits nonzero if a check fails.\nExample: seabox doctor --json | jq -e '.ok'"
+    )]
+    Doctor(DoctorArgs),
+    #[command(
+        about = "Print the idmap mount option seabox generates, and what it maps",
+        long_about = "Print the idmap mount option seabox generates for mounted directories with the current host uid and gid, and which host ids it maps to which container ids. Useful to debug the ownership of files in mounted directories.\nExample: seabox explain-idmap --uid 1001 --gid 1001"
+    )]
+    ExplainIdmap(ExplainIdmapArgs),
+    // #[clap(subcommand)]
+    Config {
+        #[command(subcommand)]
+        inner: Option<ConfigSubcommand>,
+    },
+}
+
+impl Commands {
+    /// Arguments shared by all commands, for commands that take them
+    fn all_args(&self) -> Option<&AllCommandArgs> {
+        match self {
+            Commands::Create(args) => Some(&args.all),
+            Commands::Enter(args) => Some(&args.all),
+            Commands::Remove(args) => Some(&args.all),
+            Commands::Temp(args) => Some(&args.all),
+            Commands::List(args) => Some(&args.all),
+            Commands::Restart(args) => Some(&args.all),
+            Commands::Stop(args) => Some(&args.all),
+            Commands::Up(args) => Some(&args.all),
+            Commands::Down(args) => Some(&args.all),
+            Commands::Update(args) => Some(&args.all),
+            Commands::Recreate(args) => Some(&args.all),
+            Commands::Prune(args) => Some(&args.all),
+            Commands::Doctor(args) => Some(&args.all),
+            Commands::Which(_) | Commands::ExplainIdmap(_) | Commands::Config { .. } => None,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    pub name: String,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "Let 'seabox prune --expired' remove the container after DURATION",
+        long_help = "Record an expiry time DURATION from now, such as 30m, 2h or 7d, after which 'seabox prune --expired' removes the container. Nothing removes it automatically\nExample: seabox create --rm-after 2h scratch"
+    )]
+    pub rm_after: Option<String>,
+
+    #[arg(
+        long,
+        help = "Set up the container without entering it",
+        long_help = "Create the container and set up its user, then exit instead of entering it"
+    )]
+    pub no_enter: bool,
+
+    #[arg(
+        long,
+        requires = "dry_run",
+        help = "With --dry-run, show how the container would differ from an existing one",
+        long_help = "With --dry-run, print how the configuration of the container would differ from that of an existing container with the same name, such as a changed image or an added mount, to judge the impact of recreating it. The existing configuration is the one recorded when it was created, or else reconstructed from the container"
+    )]
+    pub diff: bool,
+
+    #[command(flatten)]
+    pub common: CreateAndTempSharedArgs,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct EnterArgs {
+    pub name: String,
+
+    #[arg(
+        short,
+        long,
+        help = "Enter the container as the given user",
+        long_help = "Enter the container as the given user, as 'name', 'uid', 'uid:gid' or 'name:group', where 'uid:' uses the user's primary group. Names are checked against the container's /etc/passwd, numeric ids are passed on as-is. Defaults to the user set up on creation"
+    )]
+    pub user: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        conflicts_with = "user",
+        help = "Enter the container as root, the same as --user root"
+    )]
+    pub root: bool,
+
+    #[arg(long, help = "Run even inside a seabox container")]
+    pub force: bool,
+
+    #[arg(short, long)]
+    pub shell: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        value_name = "PATH",
+        group = "start_dir",
+        help = "Start in the given directory of the container",
+        long_help = "Start in the given absolute directory of the container, instead of the directory corresponding to the current directory"
+    )]
+    pub workdir: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SUBDIR",
+        group = "start_dir",
+        help = "Start in the given subdirectory of the mount, wherever seabox is run from",
+        long_help = "Start in the given subdirectory of the directory mounted into the container, instead of the one corresponding to the current directory. It isn't checked on the host, as it only has to exist in the container\nExample: seabox enter dev --path src/lib"
+    )]
+    pub path: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "start_dir",
+        help = "Start in the container's default working directory",
+        long_help = "Start in the default working directory of the container instead of the directory corresponding to the current directory, for when that directory isn't accessible to the container user"
+    )]
+    pub no_workdir: bool,
+
+    #[arg(
+        long,
+        requires = "start_dir",
+        help = "Create the --workdir or --path directory if it doesn't exist, owned by the entering user"
+    )]
+    pub mkdir: bool,
+
+    #[arg(
+        long,
+        help = "Fail instead of starting the container if it isn't running"
+    )]
+    pub no_start: bool,
+
+    #[arg(
+        long,
+        value_name = "KEYS",
+        help = "Key sequence for detaching from the container",
+        long_help = "Key sequence for detaching from the container, overriding the detach_keys config value. An empty value disables detaching"
+    )]
+    pub detach_keys: Option<String>,
+
+    #[arg(
+        long,
+        help = "Offer to recreate the container if its image changed since it was created",
+        long_help = "Offer to recreate the container before entering it if the local image of its tag differs from the image it was created from, such as after pulling a moving tag like latest. Recreating discards the writable layer of the container"
+    )]
+    pub replace_on_image_change: bool,
+
+    #[arg(
+        short,
+        long,
+        requires = "replace_on_image_change",
+        help = "Recreate without asking when the image changed"
+    )]
+    pub yes: bool,
+
+    #[arg(
+        short,
+        long,
+        value_name = "KEY[=VALUE]",
+        help = "Set an environment variable for this session",
+        long_help = "Set an environment variable for this session only. 'KEY' alone forwards the value of KEY from the host environment. Can be specified multiple times\nExample: seabox enter --env DEBUG=1 --env TERM dev"
+    )]
+    pub env: Vec<String>,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct RemoveArgs {
+    pub names: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "Also remove anonymous volumes of the containers",
+        long_help = "Also remove anonymous volumes the containers created, such as those declared by the image. Named volumes and mounted host directories are left alone. Defaults to the remove_volumes config value"
+    )]
+    pub volumes: bool,
+
+    #[arg(
+        long,
+        help = "Also remove the image of the containers",
+        long_help = "Also remove the image the containers were created from, unless other containers still use it"
+    )]
+    pub remove_image: bool,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct RestartArgs {
+    pub names: Vec<String>,
+
+    #[arg(
+        long = "all",
+        conflicts_with = "names",
+        help = "Restart every container created by seabox"
+    )]
+    pub all_boxes: bool,
+
+    #[arg(
+        long,
+        help = "Wait until restarted containers are running and healthy",
+        long_help = "Wait until restarted containers are running and, for images with a healthcheck, healthy. Fails if a container isn't ready within --wait-timeout"
+    )]
+    pub wait: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 60,
+        requires = "wait",
+        help = "How long --wait waits for each container"
+    )]
+    pub wait_timeout: u64,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct StopArgs {
+    pub names: Vec<String>,
+
+    #[arg(
+        long = "all",
+        conflicts_with = "names",
+        help = "Stop every container created by seabox"
+    )]
+    pub all_boxes: bool,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct UpdateArgs {
+    pub name: String,
+
+    #[arg(
+        long,
+        help = "Update even if the image is outside of allowed_image_prefixes",
+        long_help = "Update the container even if its image doesn't start with one of the allowed_image_prefixes of the config file. Not needed for containers created with --allow-any-image, as that is recorded"
+    )]
+    pub allow_any_image: bool,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct RecreateArgs {
+    pub name: String,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct WhichArgs {
+    pub name: String,
+
+    #[arg(long, value_enum, default_value_t = WhichField::Id)]
+    pub field: WhichField,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum WhichField {
+    /// Container id
+    Id,
+    /// Host directory mounted into the container
+    Mount,
+    /// Image the container was created from
+    Image,
+    /// User the container runs as
+    User,
+}
+
+#[derive(Args)]
+pub struct UpArgs {
+    #[arg(value_name = "FILE")]
+    pub file: String,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct DownArgs {
+    #[arg(value_name = "FILE")]
+    pub file: String,
+
+    #[arg(short, long, help = "Also remove anonymous volumes of the containers")]
+    pub volumes: bool,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct ExplainIdmapArgs {
+    #[arg(long, help = "Explain the idmap of --root containers")]
+    pub root: bool,
+
+    #[arg(
+        long,
+        value_name = "UID",
+        conflicts_with = "root",
+        help = "Uid of the container user [default: 1000]"
+    )]
+    pub uid: Option<i64>,
+
+    #[arg(
+        long,
+        value_name = "GID",
+        conflicts_with = "root",
+        help = "Gid of the container user [default: 1000]"
+    )]
+    pub gid: Option<i64>,
+}
+
+#[derive(Args)]
+pub struct PruneArgs {
+    #[arg(long, help = "Remove all cache volumes created with --mount-cache")]
+    pub caches: bool,
+
+    #[arg(
+        long,
+        help = "Remove containers created with --rm-after that have expired"
+    )]
+    pub expired: bool,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct DoctorArgs {
+    #[arg(
+        long,
+        help = "Print the checks as a JSON object",
+        long_help = "Print the checks as a JSON object with an 'ok' boolean and a 'checks' array of {check, status, detail} objects, where status is ok, warn or fail"
+    )]
+    pub json: bool,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct TempArgs {
+    #[command(flatten)]
+    pub common: CreateAndTempSharedArgs,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Run a script instead of an interactive shell, '-' reads it from stdin",
+        long_help = "Run the script at PATH with /bin/sh -c instead of an interactive shell, after the container user is set up. '-' reads the script from stdin. seabox exits with the exit code of the script.\nExample: echo 'cargo test' | seabox tmp --script -"
+    )]
+    pub script: Option<String>,
+
+    #[arg(
+        long,
+        help = "Name of the temporary container",
+        long_help = "Name of the temporary container, instead of a name generated by podman. The container is removed on exit, and removed explicitly if it lingers, so the name can be reused right away"
+    )]
+    pub name: Option<String>,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    #[arg(long, help = "Print the containers as a JSON array")]
+    pub json: bool,
+
+    #[arg(
+        short = 'q',
+        long,
+        conflicts_with = "json",
+        help = "Print only box names, one per line"
+    )]
+    pub names_only: bool,
+
+    #[arg(
+        long,
+        help = "List boxes of both the rootful and the rootless podman store",
+        long_help = "List boxes of both the rootful and the rootless podman store, which keep separate containers, annotating each box with its store. Boxes listed by both, as when running as root, are shown once"
+    )]
+    pub all_stores: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["names_only", "all_stores"],
+        help = "List the images boxes use, with how many boxes use each",
+        long_help = "List the images boxes were created from instead of the boxes, with how many boxes and which ones use each image and its size. With --json, prints an array of {Image, Boxes, Names, Size} objects"
+    )]
+    pub images: bool,
+
+    #[command(flatten)]
+    pub all: AllCommandArgs,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigSubcommand {
+    Show {
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ConfigFormat::Toml,
+            help = "Format to print the config file in",
+            long_help = "Format to print the config file in. 'toml' prints the file as-is, 'json' and 'yaml' parse and convert it, dropping comments"
+        )]
+        format: ConfigFormat,
+    },
+    #[command(
+        about = "Remove a key from the config file",
+        long_about = "Remove a key from the config file and rewrite it. Use 'IMAGE.KEY' for keys of an image profile and 'presets.NAME.KEY' for keys of a preset.\nExample: seabox config unset docker.io/library/alpine:latest.install_sudo"
+    )]
+    Unset { key: String },
+    /// Print a JSON schema of the config file, for editor completion
+    #[command(hide = true)]
+    Schema,
+    #[command(
+        about = "Write a commented starter config file",
+        long_about = "Write a commented starter config file with an example image, sudo command and image profile to the config file path, creating its directory"
+    )]
+    Init {
+        #[arg(long, help = "Overwrite an existing config file")]
+        force: bool,
+    },
+    #[command(about = "Print the directories seabox stores its config and data in")]
+    Dirs {
+        #[arg(long, help = "Print the directories as JSON")]
+        json: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+#[derive(Args, Clone)]
+pub struct AllCommandArgs {
+    #[arg(long, default_value = "false")]
+    pub dry_run: bool,
+
+    #[arg(long, default_value = "false")]
+    pub verbose: bool,
+
+    #[arg(long, help = "Don't show progress messages")]
+    pub quiet: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DryRunOutput::Text,
+        requires = "dry_run",
+        help = "How --dry-run prints commands",
+        long_help = "How --dry-run prints commands: 'text' prints shell-quoted command lines, 'json' prints one JSON object per line with the exact arguments and the purpose of the command, like {\"argv\": [\"podman\", \"run\", ...], \"purpose\": \"create\"}"
+    )]
+    pub output: DryRunOutput,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum DryRunOutput {
+    Text,
+    Json,
+}
+
+#[derive(Args, Default, Debug, serde::Deserialize, serde::Serialize)]
+pub struct CreateAndTempSharedArgs {
+    #[serde(skip)]
+    #[arg(
+        long,
+        help = "Apply a named preset from the config file",
+        long_help = "Apply a named preset defined in a [presets.<name>] table of the config file. Preset values take precedence over the config file, image profiles and environment variables, but not over CLI flags."
+    )]
+    pub preset: Option<String>,
+
+    #[serde(skip)]
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Read settings from a JSON or TOML file, '-' reads stdin",
+        long_help = "Read settings from a JSON or TOML document with the keys of the config file, such as image, volume and ulimit. '-' reads it from stdin. Spec values take precedence over presets, but not over CLI flags. Unknown keys are an error\nExample: echo '{\"image\": \"docker.io/library/alpine\"}' | seabox create --spec - dev"
+    )]
+    pub spec: Option<String>,
+
+    #[serde(skip)]
+    #[arg(long, help = "Run even inside a seabox container")]
+    pub force: bool,
+
+    // Recorded, so recreating the box doesn't reject its image
+    #[serde(default)]
+    #[arg(
+        long,
+        help = "Use an image outside of allowed_image_prefixes",
+        long_help = "Use the image even if it doesn't start with one of the allowed_image_prefixes of the config file. Also needed for --build while allowed_image_prefixes is set, as the base images of a Containerfile aren't checked"
+    )]
+    pub allow_any_image: bool,
+
+    #[serde(skip)]
+    #[arg(
+        long,
+        value_name = "CONTEXT",
+        conflicts_with = "image",
+        help = "Build the image from a Containerfile in CONTEXT",
+        long_help = "Build the image from the Containerfile or Dockerfile in the CONTEXT directory with 'podman build', and create the container from it. The image is tagged localhost/seabox-build-NAME\nExample: seabox create --build . dev"
+    )]
+    pub build: Option<String>,
+
+    #[serde(skip)]
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        requires = "build",
+        help = "Set a build argument for --build",
+        long_help = "Set a build argument for --build, passed to 'podman build --build-arg'. Can be specified multiple times\nExample: seabox create --build . --build-arg VERSION=1.2 dev"
+    )]
+    pub build_arg: Vec<String>,
+
+    #[serde(skip)]
+    #[arg(
+        long,
+        value_name = "STAGE",
+        requires = "build",
+        help = "Build the given stage of a multi-stage Containerfile with --build"
+    )]
+    pub build_target: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(short, long)]
+    pub image: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(short, long)]
+    pub shell: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(short, help = "Host directory to mount to /mount in the container")]
+    pub directory: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        short,
+        long,
+        help = "Add additional mounts manually",
+        long_help = "Add additional mounts with the format 'host_directory:container_directory'. A single 'path' mounts the host path to the same path in the container. A ':z' or ':Z' suffix relabels the mount for SELinux, like --relabel shared or private. An ':idmap=HOST-CONTAINER-COUNT' suffix maps ids of this mount differently from the others, ':idmap=none' doesn't idmap it. Can be specified multiple times. Mounts listed in the SEABOX_VOLUMES environment variable, separated by ';', are added to these"
+    )]
+    pub volume: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        short,
+        long,
+        allow_hyphen_values = true,
+        help = "Additional arguments to pass to Podman",
+        long_help = "Pass additional arguments to Podman - the string is broken into individual arguments using shell string parsing with shlex. They are placed before the flags seabox generates, so they can't override them - use --pass-through-end for that.\nExample: seabox create -p \"--pidfile /tmp/pidfile --cidfile /tmp/cidfile\" test"
+    )]
+    pub pass_through: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        help = "Additional arguments to pass to Podman after the generated flags",
+        long_help = "Like --pass-through, but the arguments are placed after the flags seabox generates and right before the container name and image. For flags podman only takes once, the last occurrence wins, so these override seabox's own choices.\nExample: seabox create --pass-through-end \"--network bridge -u 0:0\" test"
+    )]
+    pub pass_through_end: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        short,
+        long,
+        help = "Use root user in container",
+        long_help = "Use the root user in the container. Typically, in case the container doesn't already have an \"normal\" user (id >= 1000), one would be created and given sudo permissions so as to act as a counterpart to the host user. This flag results in such a user not being created.",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub root: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Attempt to install sudo + su in the container. Useful when using base distro images",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub install_sudo: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Skip creation of password for user",
+        aliases = ["no-passwd"],
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub no_password: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Edit /etc/sudoers file in the container to allow passwordless sudo. WARNING: this gives programs running in the container access to root on the system with no password. Implies --no-password.",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub unsafe_setup_passwordless_sudo: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Pull the latest version of container image.",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub pull: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Let podman add a /etc/passwd entry for the container user [default: false]",
+        long_help = "Pass --passwd=true to podman, so it adds an /etc/passwd entry for the container user if the image has none. The init script then uses that entry instead of creating its own user, so the user is named after podman's entry rather than the current user. Useful for images without useradd or adduser [default: false]",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub passwd: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Don't let podman manage /etc/hosts in the container",
+        long_help = "Pass --no-hosts to podman so /etc/hosts from the image is left untouched. This also skips the generated --add-host entry for the container hostname.",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub no_hosts: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Host file to mount as /etc/hosts in the container",
+        long_help = "Mount the given host file read-only as /etc/hosts in the container. This skips the generated --add-host entry for the container hostname."
+    )]
+    pub hosts_file: Option<String>,
+
+    // Added to the hosts of the config rather than replacing them, so not merged by figment
+    #[serde(skip)]
+    #[arg(
+        long,
+        value_name = "NAME:IP",
+        help = "Add an entry to /etc/hosts in the container",
+        long_help = "Add an entry to /etc/hosts in the container, in addition to the hosts config value. Can be specified multiple times\nExample: seabox create --add-host registry.internal:10.0.0.5 dev"
+    )]
+    pub add_host: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Security options to pass to podman",
+        long_help = "Security options to pass to podman with --security-opt, such as 'seccomp=/path/profile.json', 'label=disable' or 'apparmor=unconfined'. Can be specified multiple times"
+    )]
+    pub security_opt: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "NAME[,OPTIONS]",
+        help = "Make a podman secret available in the container",
+        long_help = "Make a secret created with 'podman secret create' available in the container, passed to podman with --secret. By default it is mounted at /run/secrets/NAME; options like type=env,target=VAR follow the name. Secrets stay out of the image and the container config. Can be specified multiple times\nExample: seabox create --secret ssh_key,type=mount,mode=0400 dev"
+    )]
+    pub secret: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Prevent processes in the container from gaining additional privileges",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub no_new_privileges: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Network mode of the container, defaults to host",
+        long_help = "Network mode of the container, passed to podman with --network. Defaults to 'host'.\nExample: seabox create --network my_network db"
+    )]
+    pub network: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Add a network-scoped alias for the container",
+        long_help = "Add a network-scoped alias for the container, so other containers on the same network can reach it by that name. Ignored with host networking. Can be specified multiple times"
+    )]
+    pub network_alias: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long, help = "Hostname of the container, defaults to seabox-<name>")]
+    pub hostname: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Mount the host directory into the home directory of the container user",
+        long_help = "Mount the host directory to <home>/<directory name> instead of /mount, and start there. The home directory is read from the image's /etc/passwd, so tools writing to $HOME live next to the code.",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub mount_into_home: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        alias = "mount-container-path-relative",
+        conflicts_with = "mount_into_home",
+        help = "Mount the host directory to the same absolute path in the container",
+        long_help = "Mount the host directory to the same absolute path in the container instead of /mount, and start there, so absolute paths recorded by debuggers, compile databases and IDEs are valid on both sides\nExample: seabox create -d ~/project --mount-at-host-path dev",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub mount_at_host_path: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Mount host directories without idmapping",
+        long_help = "Mount host directories without idmapping, for kernels or filesystems that don't support idmapped mounts. Mounted files keep their host ownership in the container.",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub no_idmap: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Retry without idmapping if idmapped mounts are not supported",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub auto_fallback: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        help = "Override the entrypoint of the image, an empty value resets it",
+        long_help = "Override the entrypoint of the image. An empty value (--entrypoint '') resets it, for images whose entrypoint wraps or ignores the container command. This mostly matters for temp containers, whose command is the init script."
+    )]
+    pub entrypoint: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "NAME=SOFT:HARD",
+        help = "Resource limits to pass to podman",
+        long_help = "Resource limits to pass to podman with --ulimit, as 'name=soft:hard' or 'name=value'. -1 means unlimited, 'host' copies the limits of the host. Can be specified multiple times\nExample: seabox create --ulimit nofile=65536:65536 db"
+    )]
+    pub ulimit: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Run a minimal init process as PID 1 to reap zombie processes",
+        long_help = "Pass --init to podman, which runs a minimal init process as PID 1 of the container that forwards signals and reaps zombie processes. This is unrelated to the script seabox runs on first entry to set up the container user.",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub init: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "NAME:CONTAINER_PATH",
+        help = "Mount a named cache volume shared between containers",
+        long_help = "Mount the named cache volume to CONTAINER_PATH, creating it if it doesn't exist. Cache volumes outlive containers and are shared by every container that mounts them, which suits build caches like ~/.cargo. They are chowned to the container user on mount. Remove them with 'seabox prune --caches'. Can be specified multiple times\nExample: seabox create --mount-cache cargo:/home/user/.cargo rust"
+    )]
+    pub mount_cache: Option<Vec<String>>,
+
+    #[serde(skip)]
+    #[arg(
+        long,
+        conflicts_with = "rootless",
+        help = "Run podman rootful through the sudo command, the default"
+    )]
+    pub rootful: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Run podman rootless as the current user instead of through the sudo command",
+        long_help = "Run podman rootless as the current user instead of rootful through the sudo command. Rootless and rootful podman keep separate container storage, so seabox remembers the mode of each container and uses it for later commands. Unrelated to --root, which chooses the user inside the container.",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub rootless: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        help = "Add a label to the container",
+        long_help = "Add a label to the container, alongside the labels seabox sets. Can be specified multiple times"
+    )]
+    pub label: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Read labels from a file of key=value lines",
+        long_help = "Read labels from a file with a 'key=value' label on each line. Blank lines and lines starting with '#' are ignored. The labels are added to those given with --label"
+    )]
+    pub label_file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "SIGNAL",
+        help = "Signal sent to the container when it is stopped",
+        long_help = "Signal sent to the container when it is stopped by stop, restart or rm, as a name such as SIGINT or a number. The container then gets the --stop-timeout grace period, 10 seconds by default, to shut down before it is killed. Without a stop signal, containers are killed immediately"
+    )]
+    pub stop_signal: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Seconds the container gets to shut down when it is stopped [default: 10 with --stop-signal, otherwise 0]",
+        long_help = "Seconds the container gets to shut down after the stop signal before it is killed, when it is stopped by stop, restart or rm. An interrupted or lingering temp container is also stopped with this grace period before it is removed, so processes can flush writes to mounted files [default: 10 with --stop-signal, otherwise 0]"
+    )]
+    pub stop_timeout: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "RUNTIME",
+        help = "OCI runtime for podman to run the container with",
+        long_help = "OCI runtime for podman to run the container with, such as crun, runc or youki, by name or path. Defaults to podman's configured runtime"
+    )]
+    pub runtime: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "DRIVER",
+        help = "Log driver for podman to record the output of the container with",
+        long_help = "Log driver for podman to record the output of the container with, such as journald, k8s-file or none. Defaults to podman's configured driver"
+    )]
+    pub log_driver: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        help = "Option of the log driver",
+        long_help = "Option of the log driver, passed to podman with --log-opt, such as max-size=10m to rotate the logs of long-running containers. Can be specified multiple times\nExample: seabox create --log-driver k8s-file --log-opt max-size=10m daemon"
+    )]
+    pub log_opt: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "SPEC",
+        help = "Add a mount with podman's --mount syntax",
+        long_help = "Add a mount with podman's full --mount syntax, passed through as-is. Bind mounts without an idmap option are idmapped like --volume mounts, unless --no-idmap is set. Can be specified multiple times\nExample: seabox create --mount type=bind,source=/srv/data,destination=/data,ro=true dev"
+    )]
+    pub mount: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        help = "Run the container with podman --privileged",
+        long_help = "Run the container with podman --privileged, giving it all capabilities and access to host devices. Defaults to true for containers made with create and false for temp containers, which are short-lived and rarely need it",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+    )]
+    pub privileged: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_enum,
+        help = "SELinux relabeling of the host directory mounted with -d",
+        long_help = "Relabel the host directory mounted with -d for SELinux, so the container can access it on enforcing hosts. 'private' (like :Z) gives only this container access, 'shared' (like :z) lets other containers access it too. --volume mounts take a :z or :Z suffix instead"
+    )]
+    pub relabel: Option<Relabel>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "KEYS",
+        help = "Key sequence for detaching from the container",
+        long_help = "Key sequence for detaching from the container, instead of podman's default ctrl-p,ctrl-q, which conflicts with some shell keybindings and nested sessions. Applies to temp containers and to entering containers. An empty value disables detaching\nExample: seabox create --detach-keys ctrl-x,ctrl-y dev"
+    )]
+    pub detach_keys: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "GROUP",
+        help = "Add a supplementary group to the container user",
+        long_help = "Add a supplementary group, by name or numeric gid, to processes in the container. Names are resolved inside the container. With idmapped mounts, a gid only grants access to mounted host files if it falls within the mapped range. Can be specified multiple times\nExample: seabox create --group-add docker dev"
+    )]
+    pub group_add: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "UID",
+        help = "Host uid that idmapped mounts map the container user to",
+        long_help = "Host uid that idmapped mounts map the container user to, instead of the uid running seabox. Mounted host files must be owned by this uid for the container user to access them as their owner\nExample: seabox create --host-uid 1500 dev"
+    )]
+    pub host_uid: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "GID",
+        help = "Host gid that idmapped mounts map the container user's group to",
+        long_help = "Host gid that idmapped mounts map the container user's group to, instead of the gid running seabox. Mounted host files must be owned by this gid for group access from the container\nExample: seabox create --host-gid 1500 dev"
+    )]
+    pub host_gid: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "SIZE",
+        help = "Memory limit of the container, like 512m or 4g",
+        long_help = "Memory limit of the container, as a number of bytes with an optional b, k, m or g unit\nExample: seabox create --memory 4g build"
+    )]
+    pub memory: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "SIZE",
+        allow_hyphen_values = true,
+        help = "Limit of memory plus swap of the container, -1 for unlimited swap",
+        long_help = "Limit of memory plus swap of the container, in the format of --memory, or -1 for unlimited swap. Requires --memory and must be at least as large; equal to it disables swap\nExample: seabox create --memory 4g --memory-swap 4g build"
+    )]
+    pub memory_swap: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "0-100",
+        value_parser = clap::value_parser!(u8).range(0..=100),
+        help = "How readily the kernel swaps out memory of the container"
+    )]
+    pub memory_swappiness: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "NUMBER",
+        help = "Number of CPUs the container can use, like 2 or 1.5"
+    )]
+    pub cpus: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "LIST",
+        help = "CPUs the container is pinned to, like 0-3 or 0,2,4",
+        long_help = "CPUs the container is pinned to, as a comma separated list of CPU indices and ranges like 0-3 or 0,2,4. Combined with --cpus, the container uses at most that much CPU time spread over these CPUs\nExample: seabox create --cpuset-cpus 2-3 bench"
+    )]
+    pub cpuset_cpus: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "SHARES",
+        value_parser = clap::value_parser!(u64).range(2..=262144),
+        help = "Relative CPU weight of the container when CPUs are contended [default: 1024]"
+    )]
+    pub cpu_shares: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Host rc file for shells in the container to source",
+        long_help = "Mount the host file at PATH read-only into the container, and have default shells source it: bash with --rcfile, POSIX shells through $ENV. Other shells ignore it\nExample: seabox create --shell-rcfile ~/.config/seabox/bashrc dev"
+    )]
+    pub shell_rcfile: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Set up the container user with a custom init script",
+        long_help = "Set up the container user with the script at PATH instead of the built-in one, for example to install extra packages. seabox replaces the same INSERT_* placeholders as in the built-in script, of which INSERT_CREATE_USER, INSERT_NEW_USERNAME, INSERT_CONTAINER_ID and INSERT_SETUP_ONLY are required\nExample: seabox create --init-script ~/.config/seabox/init.sh dev"
+    )]
+    pub init_script: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "TIMEZONE",
+        help = "Timezone of the container, like Europe/Berlin, or 'host'",
+        long_help = "Set the TZ environment variable of the container to TIMEZONE, like Europe/Berlin. 'host' mounts /etc/localtime of the host read-only and uses the host's timezone name. Sessions of enter can still override TZ with --env\nExample: seabox create --tz host dev"
+    )]
+    pub tz: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+        help = "Mount the host's /etc/resolv.conf read-only into the container",
+        long_help = "Mount the host's /etc/resolv.conf read-only into the container, for networks other than host that need the host's DNS setup. Skipped with host networking, where the host's resolv.conf is already in effect"
+    )]
+    pub copy_resolv_conf: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Mount the given file read-only as /etc/resolv.conf in the container",
+        long_help = "Mount the given host file read-only as /etc/resolv.conf in the container, taking precedence over copy_resolv_conf. Skipped with host networking\nExample: seabox create --network bridge --resolv-conf ~/corp-resolv.conf dev"
+    )]
+    pub resolv_conf: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long,
+        value_name = "MODE",
+        help = "PID namespace of the container, like container:NAME to share another box's",
+        long_help = "PID namespace of the container, passed to podman --pid: 'host', 'private', 'ns:PATH' or 'container:NAME'. 'container:NAME' shares the PID namespace of another seabox container, so a debug box can see its processes\nExample: seabox create --pid container:dev debug"
+    )]
+    pub pid: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        long = "no-privileged-warning",
+        value_parser = clap::builder::BoolishValueParser::new(), num_args(0..=1), default_missing_value = "true",
+        help = "Don't warn when creating a privileged container"
+    )]
+    pub suppress_privileged_warning: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(
+        short,
+        long,
+        value_name = "PATH",
+        help = "Working directory of the container, instead of /mount",
+        long_help = "Absolute working directory of the container, instead of the directory mounted with -d. Without -d, the destination of the first --volume on the command line is used by default. enter still starts in the directory corresponding to the current directory when one is mounted with -d\nExample: seabox tmp -v ~/src:/work -w /work"
+    )]
+    pub workdir: Option<String>,
+}
+
+/// Metadata seabox records about a box it created, stored in the data directory
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct BoxInstanceFormat {
+    pub name: String,
+    pub image: String,
+    /// Whether the box was created with rootful podman
+    pub rootful: bool,
+    /// Unix time after which `prune --expired` removes the box
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<u64>,
+    /// Id of the image when the box was created, to notice when its tag moves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_id: Option<String>,
+}
+
+/// Everything a box was created from, stored next to its metadata so it can be recreated
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct BoxLockFormat {
+    pub name: String,
+    /// Arguments given on the command line
+    pub args: CreateAndTempSharedArgs,
+    /// Configuration after merging the config file, environment, presets and arguments
+    pub config: Config,
+}
+
+/// Output of `podman container inspect`. Right after creation, podman can report a container
+/// before all of it is filled in, so everything but the name of the image may be missing.
+#[derive(serde::Deserialize)]
+pub struct PodmanContainerInspectFormat {
+    #[serde(rename = "Id", default)]
+    pub id: String,
+
+    #[serde(rename = "Mounts", default)]
+    pub mounts: Vec<MountType>,
+
+    #[serde(rename = "State", default)]
+    pub state: StateType,
+
+    #[serde(rename = "Config", default)]
+    pub config: ConfigType,
+
+    #[serde(rename = "ImageName")]
+    pub image_name: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct MountType {
+    #[serde(rename = "Type", default)]
+    pub kind: String,
+
+    #[serde(rename = "Name", default)]
+    pub name: String,
+
+    #[serde(rename = "Source")]
+    pub source: String,
+
+    #[serde(rename = "Destination")]
+    pub destination: String,
+}
+#[derive(Default, serde::Deserialize)]
+pub struct StateType {
+    #[serde(rename = "Running", default)]
+    pub running: bool,
+
+    /// Only set for images with a healthcheck, older podman versions call it Healthcheck
+    #[serde(rename = "Health", alias = "Healthcheck", default)]
+    pub health: Option<HealthType>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct HealthType {
+    #[serde(rename = "Status")]
+    pub status: String,
+}
+
+#[derive(Default, serde::Deserialize)]
+pub struct ConfigType {
+    #[serde(rename = "User", default)]
+    pub user: String,
+
+    #[serde(rename = "Labels")]
+    pub labels: Option<HashMap<String, String>>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PodmanImageInspectFormat {
+    #[serde(rename = "Id", default)]
+    pub id: String,
+
+    #[serde(rename = "Size", default)]
+    pub size: Option<u64>,
+
+    #[serde(rename = "Labels")]
+    pub labels: Option<HashMap<String, String>>,
+}
+
+fn project_dirs() -> directories::ProjectDirs {
+    directories::ProjectDirs::from("rs", "", SEABOX_NAME)
+        .expect("No home directory found for the current user")
+}
+
+pub fn get_configuration_file_path() -> String {
+    let project = project_dirs();
+
+    let config_dir = project.config_dir();
+
+    config_dir
+        .join(format!("{SEABOX_NAME}.toml"))
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Directory for cached data that can be regenerated
+pub fn get_cache_directory_path() -> String {
+    project_dirs().cache_dir().to_str().unwrap().to_string()
+}
+
+/// Directory for data seabox keeps about boxes
+pub fn get_data_directory_path() -> String {
+    project_dirs().data_dir().to_str().unwrap().to_string()
+}
+
+pub fn read_configuration_file() -> Result<ConfigFileFormat> {
+    // Returns default values of Config file if not found
+    let config_file_path = get_configuration_file_path();
+    let config_as_str = std::fs::read_to_string(&config_file_path).unwrap_or_default();
+    toml::from_str(&config_as_str).map_err(|e| {
+        SeaboxError::Config(format!(
+            "Failed to parse config file '{config_file_path}': {e}"
+        ))
+    })
+}
+
+/// Convert the contents of a config file to the given format
+pub fn format_configuration_file(contents: &str, format: ConfigFormat) -> Result<String> {
+    let parse = || {
+        toml::from_str::<ConfigFileFormat>(contents)
+            .map_err(|e| SeaboxError::Config(format!("Failed to parse config file: {e}")))
+    };
+
+    match format {
+        ConfigFormat::Toml => Ok(contents.to_string()),
+        ConfigFormat::Json => serde_json::to_string_pretty(&parse()?)
+            .map_err(|e| SeaboxError::Config(format!("Failed to convert config to JSON: {e}"))),
+        ConfigFormat::Yaml => serde_yaml::to_string(&parse()?)
+            .map_err(|e| SeaboxError::Config(format!("Failed to convert config to YAML: {e}"))),
+    }
+}
+
+/// Write the commented starter config file to `path`, unless a file exists there and `force` isn't set
+pub fn init_configuration_file_at(path: &std::path::Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        return Err(SeaboxError::Config(format!(
+            "Config file '{}' already exists - pass --force to overwrite it",
+            path.display()
+        )));
+    }
+
+    if let Some(config_dir) = path.parent() {
+        fs::create_dir_all(config_dir).map_err(|e| {
+            SeaboxError::Config(format!(
+                "Cannot create config directory '{}': {e}",
+                config_dir.display()
+            ))
+        })?;
+    }
+
+    fs::write(path, STARTER_CONFIG).map_err(|e| {
+        SeaboxError::Config(format!(
+            "Failed to write config file '{}': {e}",
+            path.display()
+        ))
+    })
+}
+
+pub fn write_configuration_file(config_file: &ConfigFileFormat) -> Result<()> {
+    write_configuration_file_to(
+        std::path::Path::new(&get_configuration_file_path()),
+        config_file,
+    )
+}
+
+/// Write the config file to the given path, creating its directory if needed
+pub fn write_configuration_file_to(
+    path: &std::path::Path,
+    config_file: &ConfigFileFormat,
+) -> Result<()> {
+    if let Some(config_dir) = path.parent() {
+        fs::create_dir_all(config_dir).map_err(|e| {
+            SeaboxError::Config(format!(
+                "Cannot create config directory '{}': {e}",
+                config_dir.display()
+            ))
+        })?;
+    }
+
+    let contents = toml::to_string_pretty(config_file)
+        .map_err(|e| SeaboxError::Config(format!("Failed to serialize config file: {e}")))?;
+
+    fs::write(path, contents).map_err(|e| {
+        SeaboxError::Config(format!(
+            "Failed to write config file '{}': {e}",
+            path.display()
+        ))
+    })
+}
+
+/// Remove a key from the contents of a config file, keeping its comments and the order of
+/// the remaining keys. `key` is a top-level key, `PROFILE.key` or `presets.NAME.key`.
+/// Returns the new contents, or None when the key wasn't set.
+pub fn unset_configuration_key(contents: &str, key: &str) -> Result<Option<String>> {
+    let parsed: ConfigFileFormat = toml::from_str(contents)
+        .map_err(|e| SeaboxError::Config(format!("Failed to parse config file: {e}")))?;
+    let mut document: toml_edit::DocumentMut = contents
+        .parse()
+        .map_err(|e| SeaboxError::Config(format!("Failed to parse config file: {e}")))?;
+
+    let (path, field) = match key.rsplit_once('.') {
+        // Profiles and presets share the top level with keys, but aren't keys themselves
+        None if key == "presets" || parsed.image_specific.contains_key(key) => return Ok(None),
+        None => (vec![], key),
+        Some((profile, field)) => match profile.strip_prefix("presets.") {
+            Some(preset) => (vec!["presets", preset], field),
+            None => (vec![profile], field),
+        },
+    };
+
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for part in &path {
+        match table.get_mut(part).and_then(|x| x.as_table_like_mut()) {
+            Some(inner) => table = inner,
+            None => return Ok(None),
+        }
+    }
+
+    if table.remove(field).is_none() {
+        return Ok(None);
+    }
+
+    // Drop tables that no longer set anything
+    for depth in (1..=path.len()).rev() {
+        let (parent, name) = (&path[..depth - 1], path[depth - 1]);
+        let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+        for part in parent {
+            table = table.get_mut(part).unwrap().as_table_like_mut().unwrap();
+        }
+
+        if table
+            .get(name)
+            .and_then(|x| x.as_table_like())
+            .is_some_and(|x| x.is_empty())
+        {
+            table.remove(name);
+        }
+    }
+
+    Ok(Some(document.to_string()))
+}
+
+fn create_config(base: &BaseConfig, profile: Option<&BaseConfig>) -> Result<Config> {
+    let unset = profile
+        .and_then(|p| p.interpolate_unset)
+        .or(base.interpolate_unset)
+        .unwrap_or_default();
+
+    let base = interpolate_config(base, unset)?;
+    let mut config = Figment::new().merge(figment::providers::Serialized::defaults(base));
+
+    if let Some(p) = profile {
+        let p = interpolate_config(p, unset)?;
+        config = config.merge(figment::providers::Serialized::defaults(p));
+    }
+
+    config
+        .merge(Env::prefixed("SEABOX_"))
+        .extract()
+        .map_err(|e| SeaboxError::Config(format!("Invalid configuration: {e}")))
+}
+
+/// Expand environment variables referenced in the string values of a config file table.
+/// The file itself is left untouched, so rewriting it keeps the references.
+fn interpolate_config(config: &BaseConfig, unset: UnsetVariables) -> Result<BaseConfig> {
+    fn interpolate_value(value: &mut serde_json::Value, unset: UnsetVariables) -> Result<()> {
+        match value {
+            serde_json::Value::String(s) => *s = interpolate(s, unset)?,
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    interpolate_value(value, unset)?;
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for value in map.values_mut() {
+                    interpolate_value(value, unset)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    let mut value = serde_json::to_value(config)
+        .map_err(|e| SeaboxError::Config(format!("Failed to read config values: {e}")))?;
+    interpolate_value(&mut value, unset)?;
+
+    serde_json::from_value(value)
+        .map_err(|e| SeaboxError::Config(format!("Invalid configuration: {e}")))
+}
+
+/// Expand `${VAR}` and `$VAR` from the environment. `$$` is a literal `$`.
+pub fn interpolate(value: &str, unset: UnsetVariables) -> Result<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+
+        let name = match chars.peek() {
+            Some('$') => {
+                chars.next();
+                result.push('$');
+                continue;
+            }
+            Some('{') => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => {
+                            return Err(SeaboxError::Config(format!(
+                                "Unterminated '${{' in config value '{value}'"
+                            )));
+                        }
+                    }
+                }
+                name
+            }
+            Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                name
+            }
+            // Not a reference, such as a trailing '$'
+            _ => {
+                result.push('$');
+                continue;
+            }
+        };
+
+        match std::env::var(&name) {
+            Ok(x) => result.push_str(&x),
+            Err(_) if unset == UnsetVariables::Empty => {}
+            Err(_) => {
+                return Err(SeaboxError::Config(format!(
+                    "Environment variable '{name}' referenced in config value '{value}' is not set"
+                )));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Read the boxes of a file for `up` and `down`, as a list of [[box]] tables with a name and
+/// the arguments to create the box with. A box can apply a preset of the config file
+fn read_up_file(path: &str) -> Result<Vec<(String, CreateAndTempSharedArgs)>> {
+    #[derive(serde::Deserialize)]
+    struct UpFileFormat {
+        #[serde(rename = "box", default)]
+        boxes: Vec<toml::Table>,
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| {
+        SeaboxError::InvalidArgument(format!("Failed to read box file '{path}': {e}"))
+    })?;
+    let file: UpFileFormat = toml::from_str(&contents).map_err(|e| {
+        SeaboxError::InvalidArgument(format!("Failed to parse box file '{path}': {e}"))
+    })?;
+
+    if file.boxes.is_empty() {
+        return Err(SeaboxError::InvalidArgument(format!(
+            "Box file '{path}' defines no boxes - add a [[box]] table with a name"
+        )));
+    }
+
+    let mut boxes: Vec<(String, CreateAndTempSharedArgs)> = vec![];
+    for mut table in file.boxes {
+        let name = match table.remove("name") {
+            Some(toml::Value::String(name)) if !name.is_empty() => name,
+            _ => {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Every box in '{path}' needs a name"
+                )));
+            }
+        };
+
+        if boxes.iter().any(|(x, _)| *x == name) {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Box '{name}' is defined more than once in '{path}'"
+            )));
+        }
+
+        let preset = match table.remove("preset") {
+            Some(toml::Value::String(preset)) => Some(preset),
+            Some(_) => {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "The preset of box '{name}' in '{path}' must be a name"
+                )));
+            }
+            None => None,
+        };
+
+        let keys: Vec<String> = table.keys().cloned().collect();
+        let mut args: CreateAndTempSharedArgs =
+            toml::Value::Table(table).try_into().map_err(|e| {
+                SeaboxError::InvalidArgument(format!("Invalid box '{name}' in '{path}': {e}"))
+            })?;
+        args.preset = preset;
+
+        // Keys that didn't end up in the arguments are unknown, or can't be set from a file
+        let known = serde_json::to_value(&args)
+            .ok()
+            .and_then(|x| x.as_object().cloned())
+            .unwrap_or_default();
+        let unknown: Vec<&str> = keys
+            .iter()
+            .filter(|x| !known.contains_key(x.as_str()))
+            .map(|x| x.as_str())
+            .collect();
+        if !unknown.is_empty() {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Unknown keys in box '{name}' of '{path}': {}",
+                unknown.join(", ")
+            )));
+        }
+
+        boxes.push((name, args));
+    }
+
+    Ok(boxes)
+}
+
+/// Read a --spec document, as JSON or TOML
+fn read_spec(path: &str) -> Result<BaseConfig> {
+    let contents = if path == "-" {
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .map_err(|e| {
+                SeaboxError::InvalidArgument(format!("Failed to read spec from stdin: {e}"))
+            })?;
+        contents
+    } else {
+        fs::read_to_string(path).map_err(|e| {
+            SeaboxError::InvalidArgument(format!("Failed to read spec '{path}': {e}"))
+        })?
+    };
+
+    let table: serde_json::Map<String, serde_json::Value> =
+        if contents.trim_start().starts_with('{') {
+            serde_json::from_str(&contents).map_err(|e| {
+                SeaboxError::InvalidArgument(format!("Failed to parse spec '{path}' as JSON: {e}"))
+            })?
+        } else {
+            toml::from_str(&contents).map_err(|e| {
+                SeaboxError::InvalidArgument(format!("Failed to parse spec '{path}' as TOML: {e}"))
+            })?
+        };
+
+    // BaseConfig is flattened into the config file, so it can't deny unknown fields itself
+    let schema = schemars::schema_for!(BaseConfig);
+    let known = schema.get("properties").and_then(|x| x.as_object());
+    let mut unknown: Vec<&str> = table
+        .keys()
+        .filter(|key| known.is_some_and(|known| !known.contains_key(*key)))
+        .map(|x| x.as_str())
+        .collect();
+    unknown.sort();
+
+    if !unknown.is_empty() {
+        return Err(SeaboxError::InvalidArgument(format!(
+            "Unknown keys in spec '{path}': {}",
+            unknown.join(", ")
+        )));
+    }
+
+    serde_json::from_value(serde_json::Value::Object(table))
+        .map_err(|e| SeaboxError::InvalidArgument(format!("Invalid spec '{path}': {e}")))
+}
+
+/// The image profile of `image`, with the settings of the profiles it inherits from with `base`
+/// merged below its own
+pub fn resolve_image_profile(
+    parsed_config_file: &ConfigFileFormat,
+    image: &str,
+) -> Result<Option<BaseConfig>> {
+    let Some(profile) = parsed_config_file.image_specific.get(image) else {
+        return Ok(None);
+    };
+
+    let mut chain: Vec<(&str, &BaseConfig)> = vec![(image, profile)];
+    while let Some(base) = &chain[chain.len() - 1].1.base {
+        if chain.iter().any(|(name, _)| name == base) {
+            let cycle: Vec<&str> = chain.iter().map(|(name, _)| *name).collect();
+            return Err(SeaboxError::Config(format!(
+                "Image profiles inherit from each other in a cycle: {} -> {base}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let Some(parent) = parsed_config_file.image_specific.get(base) else {
+            return Err(SeaboxError::Config(format!(
+                "Image profile '{}' inherits from '{base}', which isn't an image profile of the config file",
+                chain[chain.len() - 1].0
+            )));
+        };
+        chain.push((base, parent));
+    }
+
+    // Unset keys aren't serialized, so each profile only overrides the keys it sets
+    let mut merged = serde_json::Map::new();
+    for (_, profile) in chain.iter().rev() {
+        if let Ok(serde_json::Value::Object(values)) = serde_json::to_value(profile) {
+            merged.extend(values);
+        }
+    }
+    merged.remove("base");
+
+    serde_json::from_value(serde_json::Value::Object(merged))
+        .map(Some)
+        .map_err(|e| SeaboxError::Config(format!("Invalid image profile '{image}': {e}")))
+}
+
+fn lookup_preset<'a>(
+    parsed_config_file: &'a ConfigFileFormat,
+    name: &str,
+) -> Result<&'a BaseConfig> {
+    match parsed_config_file.presets.get(name) {
+        Some(preset) => Ok(preset),
+        None => {
+            let mut available: Vec<&str> = parsed_config_file
+                .presets
+                .keys()
+                .map(|x| x.as_str())
+                .collect();
+            available.sort();
+
+            if available.is_empty() {
+                Err(SeaboxError::Config(format!(
+                    "Preset '{name}' not found in the config file\nNo presets are defined"
+                )))
+            } else {
+                Err(SeaboxError::Config(format!(
+                    "Preset '{name}' not found in the config file\nAvailable presets: {}",
+                    available.join(", ")
+                )))
+            }
+        }
+    }
+}
+
+/// Print each value of the config file, profile, preset or spec that a CLI flag changed
+fn report_cli_overrides(without_cli: &Config, effective: &Config) {
+    let (Ok(serde_json::Value::Object(before)), Ok(serde_json::Value::Object(after))) = (
+        serde_json::to_value(without_cli),
+        serde_json::to_value(effective),
+    ) else {
+        return;
+    };
+
+    for (key, value) in &after {
+        if let Some(previous) = before.get(key)
+            && previous != value
+        {
+            eprintln!("Command line overrides {key}: configured {previous}, effective {value}");
+        }
+    }
+}
+
+/// Merge layers over the config, in increasing precedence, and then the CLI arguments
+fn merge_overrides(
+    config: &Config,
+    layers: &[&BaseConfig],
+    cli_config_args: &CreateAndTempSharedArgs,
+) -> Result<Config> {
+    let mut figment = Figment::from(figment::providers::Serialized::defaults(config));
+
+    for layer in layers {
+        figment = figment.merge(figment::providers::Serialized::defaults(layer));
+    }
+
+    let mut config: Config = figment
+        .merge(figment::providers::Serialized::defaults(cli_config_args))
+        .extract()
+        .map_err(|e| SeaboxError::Config(format!("Invalid configuration: {e}")))?;
+
+    if cli_config_args.rootful {
+        config.rootless = false;
+    }
+
+    Ok(config)
+}
+
+impl Context {
+    /// Create a context from the user's config file and the environment
+    pub fn new() -> Result<Context> {
+        Context::from_config_file(read_configuration_file()?)
+    }
+
+    /// Create a context from an already parsed config file, merged with the environment
+    pub fn from_config_file(parsed: ConfigFileFormat) -> Result<Context> {
+        let config = create_config(&parsed.base, None)?;
+
+        Ok(Context {
+            config,
+            parsed_config_file: parsed,
+            runner: Box::new(ProcessRunner),
+            data_dir: get_data_directory_path().into(),
+            engine_env: vec![],
+            quiet: false,
+            dry_run_output: DryRunOutput::Text,
+            trace: false,
+            privileged_warned: false,
+        })
+    }
+
+    /// Use the given runner to spawn engine commands instead of running them directly
+    pub fn with_runner(mut self, runner: impl Runner + 'static) -> Context {
+        self.runner = Box::new(runner);
+        self
+    }
+
+    /// Store box metadata in the given directory instead of the user's data directory
+    pub fn with_data_dir(mut self, data_dir: impl Into<std::path::PathBuf>) -> Context {
+        self.data_dir = data_dir.into();
+        self
+    }
+
+    pub fn run(&mut self, cli: Cli) -> Result<()> {
+        self.engine_env = self
+            .config
+            .engine_env
+            .iter()
+            .chain(&cli.engine_env)
+            .map(|x| parse_engine_env(x))
+            .collect::<Result<_>>()?;
+
+        self.quiet = cli
+            .command
+            .as_ref()
+            .and_then(Commands::all_args)
+            .is_some_and(|args| args.quiet);
+
+        self.trace = cli.trace
+            || std::env::var(TRACE_ENV).is_ok_and(|x| !matches!(x.as_str(), "" | "0" | "false"));
+
+        self.dry_run_output = cli
+            .command
+            .as_ref()
+            .and_then(Commands::all_args)
+            .map_or(DryRunOutput::Text, |args| args.output);
+
+        match &cli.command {
+            Some(Commands::Create(args)) => {
+                self.resolve_config_args_create_tmp(&args.common, args.all.verbose)?;
+                if args.all.dry_run && self.dry_run_output == DryRunOutput::Text {
+                    println!("{:#?}", self.config);
+                }
+                self.handle_create(args)
+            }
+            Some(Commands::Enter(args)) => self.handle_enter(args),
+            Some(Commands::Remove(args)) => self.handle_remove(args),
+            Some(Commands::Temp(args)) => {
+                self.resolve_config_args_create_tmp(&args.common, args.all.verbose)?;
+                if args.all.dry_run && self.dry_run_output == DryRunOutput::Text {
+                    println!("{:#?}", self.config);
+                }
+                self.handle_temp(args)
+            }
+            Some(Commands::List(args)) if args.all_stores => self.handle_list_all_stores(args),
+            Some(Commands::List(args)) if args.images => self.handle_list_images(args),
+            Some(Commands::List(args)) => self.handle_list(args),
+            Some(Commands::Restart(args)) => self.handle_restart(args),
+            Some(Commands::Stop(args)) => self.handle_stop(args),
+            Some(Commands::Up(args)) => self.handle_up(args),
+            Some(Commands::Down(args)) => self.handle_down(args),
+            Some(Commands::Update(args)) => self.handle_update(args),
+            Some(Commands::Recreate(args)) => self.handle_recreate(args),
+            Some(Commands::Which(args)) => self.handle_which(args),
+            Some(Commands::Prune(args)) => self.handle_prune(args),
+            Some(Commands::Doctor(args)) => self.handle_doctor(args),
+            Some(Commands::ExplainIdmap(args)) => self.handle_explain_idmap(args),
+            Some(Commands::Config {
+                inner: Some(ConfigSubcommand::Show { format }),
+            }) => self.handle_config_show(*format),
+            Some(Commands::Config {
+                inner: Some(ConfigSubcommand::Unset { key }),
+            }) => self.handle_config_unset(key),
+            Some(Commands::Config {
+                inner: Some(ConfigSubcommand::Schema),
+            }) => self.handle_config_schema(),
+            Some(Commands::Config {
+                inner: Some(ConfigSubcommand::Dirs { json }),
+            }) => self.handle_config_dirs(*json),
+            Some(Commands::Config {
+                inner: Some(ConfigSubcommand::Init { force }),
+            }) => self.handle_config_init(*force),
+            Some(Commands::Config { inner: None }) => {
+                println!("{}", get_configuration_file_path());
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn resolve_config_args_create_tmp(
+        &mut self,
+        cli_config_args: &CreateAndTempSharedArgs,
+        verbose: bool,
+    ) -> Result<()> {
+        self.resolve_config_layers(cli_config_args, verbose)?;
+
+        // Added to, rather than replaced by, volumes of the CLI and config file
+        if let Ok(volumes) = std::env::var(VOLUMES_ENV) {
+            self.config.volume.extend(parse_volume_list(&volumes));
+        }
+
+        Ok(())
+    }
+
+    /// Resolve the config of a box without SEABOX_VOLUMES, for arguments recovered from a
+    /// container whose volumes already include them
+    fn resolve_config_layers(
+        &mut self,
+        cli_config_args: &CreateAndTempSharedArgs,
+        verbose: bool,
+    ) -> Result<()> {
+        // Config merge hierarchy:
+        // CLI > Spec > Preset > Env > Profile in config > config > defaults
+
+        // Two passes of merging config - first we need to resolve the image
+        // Once image has been resolved, insert the "image profile" into the merge hierarchy.
+
+        let preset = cli_config_args
+            .preset
+            .as_ref()
+            .map(|name| lookup_preset(&self.parsed_config_file, name))
+            .transpose()?
+            .map(|p| {
+                interpolate_config(
+                    p,
+                    p.interpolate_unset.unwrap_or(self.config.interpolate_unset),
+                )
+            })
+            .transpose()?;
+
+        let spec = cli_config_args.spec.as_deref().map(read_spec).transpose()?;
+
+        let layers: Vec<&BaseConfig> = preset.iter().chain(spec.iter()).collect();
+
+        // The config as it would be without CLI flags, to report what they override
+        let without_cli = |config: &Config| {
+            verbose
+                .then(|| merge_overrides(config, &layers, &CreateAndTempSharedArgs::default()))
+                .transpose()
+        };
+
+        let mut config_without_cli = without_cli(&self.config)?;
+        self.config = merge_overrides(&self.config, &layers, cli_config_args)?;
+
+        // If we have a profile for this image, apply it it to the config merge hierarchy
+        if let Some(cli_image) = &self.config.image.clone()
+            && let Some(profile) = resolve_image_profile(&self.parsed_config_file, cli_image)?
+        {
+            self.config = create_config(&self.parsed_config_file.base, Some(&profile))?;
+            config_without_cli = without_cli(&self.config)?;
+            self.config = merge_overrides(&self.config, &layers, cli_config_args)?;
+        }
+
+        if let Some(config_without_cli) = config_without_cli {
+            report_cli_overrides(&config_without_cli, &self.config);
+        }
+
+        self.config
+            .hosts
+            .extend(cli_config_args.add_host.iter().cloned());
+
+        Ok(())
+    }
+
+    /// Check a --pid value, and that a container it joins is an existing box
+    fn validate_pid_namespace(&self, pid: &str, dry_run: bool) -> Result<()> {
+        let Some(target) = pid.strip_prefix("container:") else {
+            if matches!(pid, "host" | "private")
+                || pid.strip_prefix("ns:").is_some_and(|x| !x.is_empty())
+            {
+                return Ok(());
+            }
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Invalid PID namespace '{pid}' - expected 'host', 'private', 'ns:PATH' or 'container:NAME'"
+            )));
+        };
+
+        if dry_run {
+            self.print_command("inspect", self.generate_container_inspect_command(target));
+        }
+
+        let info = self.inspect_container(target)?;
+        let is_box = info
+            .config
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get(SEABOX_NAME))
+            .is_some_and(|x| x == "true");
+
+        if !is_box {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Container '{target}' was not created by seabox"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Mount of the host's or a custom resolv.conf, unless host networking already shares it
+    fn resolv_conf_arguments(&self, network: &str) -> Result<Vec<String>> {
+        // An explicit file wins over copying the host's, which may come from another layer
+        let source = match (&self.config.resolv_conf, self.config.copy_resolv_conf) {
+            (Some(path), _) => path.as_str(),
+            (None, true) => "/etc/resolv.conf",
+            (None, false) => return Ok(vec![]),
+        };
+
+        let Ok(path) = fs::canonicalize(source) else {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "resolv.conf file '{source}' does not exist"
+            )));
+        };
+
+        if network == "host" {
+            eprintln!(
+                "Warning: not mounting '{source}' as /etc/resolv.conf - host networking already uses the host's resolv.conf"
+            );
+            return Ok(vec![]);
+        }
+
+        Ok(vec![
+            "--mount".to_string(),
+            format!(
+                "type=bind,source={},destination=/etc/resolv.conf,ro=true",
+                path.to_str().unwrap()
+            ),
+        ])
+    }
+
+    /// Memory and swap limits of a new container, only for those that are set
+    fn memory_arguments(&self) -> Result<Vec<String>> {
+        let mut arguments = vec![];
+
+        let memory = match &self.config.memory {
+            Some(memory) => {
+                let bytes = parse_memory_size(memory).ok_or_else(|| {
+                    SeaboxError::InvalidArgument(format!(
+                        "Invalid memory limit '{memory}' - expected a size like 512m or 4g"
+                    ))
+                })?;
+                arguments.extend(["--memory".to_string(), memory.to_string()]);
+                Some(bytes)
+            }
+            None => None,
+        };
+
+        if let Some(swap) = &self.config.memory_swap {
+            let Some(memory) = memory else {
+                return Err(SeaboxError::InvalidArgument(
+                    "--memory-swap requires --memory".to_string(),
+                ));
+            };
+
+            if swap != "-1" {
+                let bytes = parse_memory_size(swap).ok_or_else(|| {
+                    SeaboxError::InvalidArgument(format!(
+                        "Invalid memory swap limit '{swap}' - expected a size like 4g or -1"
+                    ))
+                })?;
+
+                // The limit covers memory and swap together
+                if bytes < memory {
+                    return Err(SeaboxError::InvalidArgument(format!(
+                        "Memory swap limit '{swap}' is smaller than the memory limit"
+                    )));
+                }
+            }
+
+            arguments.extend(["--memory-swap".to_string(), swap.to_string()]);
+        }
+
+        if let Some(swappiness) = self.config.memory_swappiness {
+            if swappiness > 100 {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid memory swappiness {swappiness} - expected 0 to 100"
+                )));
+            }
+            arguments.extend(["--memory-swappiness".to_string(), swappiness.to_string()]);
+        }
+
+        Ok(arguments)
+    }
+
+    fn cpu_arguments(&self) -> Result<Vec<String>> {
+        let mut arguments = vec![];
+
+        if let Some(cpus) = &self.config.cpus {
+            if !cpus.parse::<f64>().is_ok_and(|x| x.is_finite() && x > 0.0) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid CPU limit '{cpus}' - expected a positive number like 2 or 1.5"
+                )));
+            }
+            arguments.extend(["--cpus".to_string(), cpus.to_string()]);
+        }
+
+        if let Some(cpuset) = &self.config.cpuset_cpus {
+            if !is_valid_cpuset(cpuset) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid CPU set '{cpuset}' - expected CPU indices and ranges like 0-3 or 0,2,4"
+                )));
+            }
+            arguments.extend(["--cpuset-cpus".to_string(), cpuset.to_string()]);
+        }
+
+        if let Some(shares) = self.config.cpu_shares {
+            if !(2..=262144).contains(&shares) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid CPU shares {shares} - expected 2 to 262144"
+                )));
+            }
+            arguments.extend(["--cpu-shares".to_string(), shares.to_string()]);
+        }
+
+        Ok(arguments)
+    }
+
+    /// Choose the image to create a container from. With fallbacks configured, the first of the
+    /// primary image and the fallbacks that is present locally is used, otherwise the primary image is pulled.
+    fn resolve_image(
+        &self,
+        dry_run: bool,
+        verbose: bool,
+        allow_any_image: bool,
+    ) -> Result<Option<String>> {
+        let candidates: Vec<&String> = self
+            .config
+            .image
+            .iter()
+            .chain(self.config.image_fallbacks.iter())
+            .collect();
+
+        // Checked before anything is pulled
+        if !allow_any_image {
+            for image in &candidates {
+                self.check_image_allowed(image)?;
+            }
+        }
+
+        if self.config.image_fallbacks.is_empty() {
+            return Ok(self.config.image.clone());
+        }
+
+        let selected = candidates
+            .iter()
+            .find(|image| self.image_inspect(image, dry_run).is_some())
+            .or(candidates.first());
+
+        if verbose && let Some(image) = selected {
+            eprintln!("Using image '{}'", image);
+        }
+
+        Ok(selected.map(|x| x.to_string()))
+    }
+
+    /// Fail unless the image starts with one of the allowed_image_prefixes, if there are any
+    fn check_image_allowed(&self, image: &str) -> Result<()> {
+        let prefixes = &self.config.allowed_image_prefixes;
+
+        if prefixes.is_empty()
+            || prefixes
+                .iter()
+                .any(|prefix| image.starts_with(prefix.as_str()))
+        {
+            return Ok(());
+        }
+
+        let prefixes: Vec<String> = prefixes.iter().map(|x| format!("'{x}'")).collect();
+        Err(SeaboxError::InvalidArgument(format!(
+            "Image '{image}' isn't allowed - allowed_image_prefixes only permits images starting with {} - pass --allow-any-image to skip this check",
+            prefixes.join(", ")
+        )))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_create_container_command(
+        &self,
+        image: Option<String>,
+        name: &str,
+        root: bool,
+        temp: bool,
+        passthrough: Option<String>,
+        directory: Option<String>,
+        additional_mounts: Vec<String>,
+        workdir_volumes: &[String],
+        pull_image: bool,
+        dry_run: bool,
+    ) -> Result<(Vec<String>, bool, i64, i64, String)> {
+        let image: &str = {
+            if let Some(x) = &image {
+                x
+            } else {
+                return Err(SeaboxError::NoImage);
+            }
+        };
+
+        let hostname = match &self.config.hostname {
+            Some(h) => h.to_string(),
+            None => format!("{}-{}", SEABOX_NAME, name),
+        };
+
+        let network = self.config.network.as_deref().unwrap_or("host");
+
+        // (uid_t)-1 means "no change" to the kernel and is never a real id
+        for (flag, id) in [
+            ("--host-uid", self.config.host_uid),
+            ("--host-gid", self.config.host_gid),
+        ] {
+            if id == Some(u32::MAX) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid {flag} {} - not a usable id",
+                    u32::MAX
+                )));
+            }
+        }
+
+        let host_user_id = self.config.host_uid.unwrap_or_else(|| host_ids().0);
+        let host_user_gid = self.config.host_gid.unwrap_or_else(|| host_ids().1);
+
+        let mut container_user_id = DEFAULT_USER_ID;
+        let mut container_user_gid = DEFAULT_USER_ID;
+
+        let mut create_user = false;
+
+        if !root {
+            let target_uid_gid = self.determine_container_uid_gid(image, dry_run)?;
+
+            // If target uid/gid not found, we may want to create a user if not --root setting
+            // This also changes the idmap_parameters
+            match target_uid_gid {
+                Some((x, y)) => {
+                    container_user_id = x;
+                    container_user_gid = y;
+                }
+                None => {
+                    create_user = true;
+                }
+            }
+
+            if dry_run {
+                eprintln!(
+                    "Container user would have uid {container_user_id} and gid {container_user_gid}{}",
+                    if create_user {
+                        ", created by seabox"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        } else {
+            container_user_id = 0;
+            container_user_gid = 0;
+        }
+
+        let mut arguments: Vec<String> = self.engine_command(&[
+            "run",
+            "--label",
+            &format!("{}=true", SEABOX_NAME),
+            "--label",
+            &format!("{RESOLVED_UID_LABEL}={container_user_id}"),
+            "--label",
+            &format!("{RESOLVED_GID_LABEL}={container_user_gid}"),
+            "--env",
+            &format!("{IN_BOX_ENV}=1"),
+            "-it",
+        ]);
+
+        // Persistent boxes have always been privileged, temp boxes rarely need it
+        if self.config.privileged.unwrap_or(!temp) {
+            arguments.push("--privileged".to_string());
+        }
+
+        let mut labels = self.config.label.clone();
+        if let Some(path) = &self.config.label_file {
+            labels.extend(read_label_file(path)?);
+        }
+
+        for label in labels {
+            if !is_valid_label(&label) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid label '{label}' - expected 'key=value'"
+                )));
+            }
+            arguments.extend(["--label".to_string(), label]);
+        }
+
+        if pull_image {
+            arguments.push("--pull=always".to_string())
+        }
+
+        if let Some(runtime) = &self.config.runtime {
+            arguments.extend(["--runtime".to_string(), runtime.to_string()]);
+        }
+
+        if let Some(log_driver) = &self.config.log_driver {
+            arguments.extend(["--log-driver".to_string(), log_driver.to_string()]);
+        }
+
+        for log_opt in &self.config.log_opt {
+            if !is_valid_log_opt(log_opt) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid log option '{log_opt}' - expected 'key=value'"
+                )));
+            }
+            arguments.extend(["--log-opt".to_string(), log_opt.to_string()]);
+        }
+
+        if temp {
+            arguments.push("--rm".to_string());
+
+            // Only temp containers are attached to
+            if let Some(keys) = &self.config.detach_keys {
+                arguments.push(format!("--detach-keys={keys}"));
+            }
+        } else {
+            arguments.push("-d".to_string())
+        }
+
+        if let Some(passthrough) = passthrough
+            && let Some(pass_through_args) = shlex::split(&passthrough)
+        {
+            arguments.extend(pass_through_args);
+        }
+
+        let user_string = {
+            if temp {
+                "0:0"
+            } else {
+                // Keep container GID blank - otherwise the container user only has one GID
+                // &format!("{container_user_id}:{container_user_gid}")
+                &format!("{container_user_id}:")
+            }
+        };
+
+        arguments.extend(
+            [
+                "--network",
+                network,
+                "--hostname",
+                &hostname,
+                "-u",
+                user_string,
+            ]
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>(),
+        );
+        arguments.push(format!("--passwd={}", self.config.passwd));
+
+        if network == "host" {
+            if !self.config.network_alias.is_empty() {
+                eprintln!(
+                    "Warning: ignoring network aliases, they have no effect with host networking"
+                );
+            }
+        } else {
+            for alias in &self.config.network_alias {
+                arguments.extend(["--network-alias".to_string(), alias.to_string()]);
+            }
+        }
+
+        if self.config.no_hosts {
+            arguments.push("--no-hosts".to_string());
+        } else if self.config.hosts_file.is_none() {
+            arguments.extend(["--add-host".to_string(), format!("{hostname}:127.0.0.1")]);
+        }
+
+        if !self.config.hosts.is_empty()
+            && (self.config.no_hosts || self.config.hosts_file.is_some())
+        {
+            return Err(SeaboxError::InvalidArgument(
+                "Hosts entries can't be added when /etc/hosts is left untouched with no_hosts or replaced with hosts_file".to_string(),
+            ));
+        }
+
+        for host in &self.config.hosts {
+            if !is_valid_host_entry(host) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid hosts entry '{host}' - expected 'name:ip'"
+                )));
+            }
+            arguments.extend(["--add-host".to_string(), host.to_string()]);
+        }
+
+        let mut security_opts = self.config.security_opt.clone();
+        if self.config.no_new_privileges && !security_opts.iter().any(|x| x == "no-new-privileges")
+        {
+            security_opts.push("no-new-privileges".to_string());
+        }
+
+        for opt in security_opts {
+            if !is_valid_security_opt(&opt) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid security option '{opt}' - expected 'key=value', 'key:value' or 'no-new-privileges'"
+                )));
+            }
+            arguments.extend(["--security-opt".to_string(), opt]);
+        }
+
+        for secret in &self.config.secret {
+            if !is_valid_secret(secret) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid secret '{secret}' - expected 'NAME' or 'NAME,key=value,...'"
+                )));
+            }
+            arguments.extend(["--secret".to_string(), secret.to_string()]);
+        }
+
+        if self.config.init {
+            arguments.push("--init".to_string());
+        }
+
+        for group in &self.config.group_add {
+            if !is_valid_group(group) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid group '{group}' - expected a group name or numeric gid"
+                )));
+            }
+            arguments.extend(["--group-add".to_string(), group.to_string()]);
+        }
+
+        if let Some(signal) = &self.config.stop_signal {
+            if !is_valid_signal(signal) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid stop signal '{signal}' - expected a signal name such as SIGINT or a number"
+                )));
+            }
+            arguments.extend(["--stop-signal".to_string(), signal.to_string()]);
+        }
+        arguments.extend([
+            "--stop-timeout".to_string(),
+            self.stop_timeout().to_string(),
+        ]);
+
+        arguments.extend(self.memory_arguments()?);
+        arguments.extend(self.cpu_arguments()?);
+
+        for ulimit in &self.config.ulimit {
+            if !is_valid_ulimit(ulimit) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid ulimit '{ulimit}' - expected 'name=soft:hard', 'name=value' or 'host'"
+                )));
+            }
+            arguments.extend(["--ulimit".to_string(), ulimit.to_string()]);
+        }
+
+        let idmap_option: String = {
+            if self.config.no_idmap {
+                "".to_string()
+            } else {
+                idmap_option(
+                    root,
+                    (host_user_id, host_user_gid),
+                    (container_user_id, container_user_gid),
+                )
+            }
+        };
+
+        let mut workdir = self.config.workdir.clone();
+        if let Some(workdir) = &workdir
+            && !workdir.starts_with('/')
+        {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Invalid working directory '{workdir}' - expected an absolute path"
+            )));
+        }
+
+        if let Some(x) = directory {
+            let path = std::path::PathBuf::from(&x);
+            match fs::canonicalize(path) {
+                Ok(dir_to_mount) => {
+                    let target = if self.config.mount_into_home {
+                        let home =
+                            self.container_home(image, container_user_id, create_user, dry_run);
+                        let dir_name = dir_to_mount
+                            .file_name()
+                            .and_then(|x| x.to_str())
+                            .unwrap_or("project");
+                        format!("{}/{}", home.trim_end_matches('/'), dir_name)
+                    } else if self.config.mount_at_host_path {
+                        dir_to_mount
+                            .to_str()
+                            .unwrap()
+                            .trim_end_matches('/')
+                            .to_string()
+                    } else {
+                        DEFAULT_MOUNT_TARGET.to_string()
+                    };
+
+                    let dir_to_mount = String::from(dir_to_mount.to_str().unwrap());
+
+                    let relabel = match self.config.relabel {
+                        Some(relabel) => Some(relabel),
+                        None if is_selinux_enforcing() => {
+                            if self.config.auto_relabel {
+                                Some(Relabel::Private)
+                            } else {
+                                eprintln!(
+                                    "Warning: SELinux is enforcing, the container may not be able to access '{dir_to_mount}'. Pass --relabel private, or set auto_relabel = true in the config to do so automatically"
+                                );
+                                None
+                            }
+                        }
+                        None => None,
+                    };
+
+                    let mount = &format!(
+                        "type=bind,source={},destination={}/{}{}",
+                        dir_to_mount,
+                        target,
+                        idmap_option,
+                        relabel.map_or("", |x| x.mount_option())
+                    );
+
+                    arguments.extend(
+                        [
+                            "--label",
+                            &format!("{MOUNT_TARGET_LABEL}={target}"),
+                            "--mount",
+                            mount,
+                        ]
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<String>>(),
+                    );
+
+                    if workdir.is_none() {
+                        workdir = Some(format!("{target}/"));
+                    }
+                }
+                Err(_) => {
+                    return Err(SeaboxError::InvalidArgument(format!(
+                        "Directory '{x}' does not exist"
+                    )));
+                }
+            }
+        }
+
+        let mut additional_mount_strings: Vec<String> = vec![];
+
+        for mount_specifier in additional_mounts {
+            let mut values: Vec<&str> = mount_specifier.split(":").collect();
+
+            // Docker style relabel suffix, as in host:container:Z, and an idmap of this mount
+            // alone, as in host:container:idmap=0-0-1, in either order
+            let mut relabel = None;
+            let mut mount_idmap_option = None;
+            while values.len() > 1 {
+                match values[values.len() - 1] {
+                    "z" if relabel.is_none() => relabel = Some(Relabel::Shared),
+                    "Z" if relabel.is_none() => relabel = Some(Relabel::Private),
+                    x if x.starts_with("idmap=") && mount_idmap_option.is_none() => {
+                        mount_idmap_option = Some(volume_idmap_option(&x["idmap=".len()..])?);
+                    }
+                    _ => break,
+                }
+                values.pop();
+            }
+
+            let (host_dir, container_dir) = match values[..] {
+                [host_dir, container_dir] => (host_dir.to_string(), container_dir.to_string()),
+                // A single path is mounted to the same path in the container
+                [path] if !path.is_empty() => {
+                    let path = fs::canonicalize(path).map_err(|_| {
+                        SeaboxError::InvalidArgument(format!(
+                            "Mount source '{path}' does not exist"
+                        ))
+                    })?;
+                    let path = path.to_str().unwrap().to_string();
+                    (path.clone(), path)
+                }
+                _ => {
+                    return Err(SeaboxError::InvalidArgument(format!(
+                        "Invalid format for mount: {mount_specifier}"
+                    )));
+                }
+            };
+
+            // Without a -d mount, start in the first volume given to this invocation rather than
+            // the image's directory, volumes from the config file or environment don't move it
+            if workdir.is_none() && workdir_volumes.contains(&mount_specifier) {
+                workdir = Some(container_dir.clone());
+            }
+
+            additional_mount_strings.extend(vec![
+                "--mount".to_string(),
+                format!(
+                    "type=bind,source={},destination={}{}{}",
+                    host_dir,
+                    container_dir,
+                    mount_idmap_option.as_deref().unwrap_or(&idmap_option),
+                    relabel.map_or("", |x| x.mount_option())
+                )
+                .to_string(),
+            ]);
+        }
+
+        arguments.extend(additional_mount_strings);
+
+        if let Some(workdir) = workdir {
+            arguments.extend(["-w".to_string(), workdir]);
+        }
+
+        for spec in &self.config.mount {
+            arguments.extend(["--mount".to_string(), mount_with_idmap(spec, &idmap_option)]);
+        }
+
+        for cache in &self.config.mount_cache {
+            let (volume, container_dir) = parse_cache_spec(cache)?;
+            // Chown the volume to the container user, volumes can't be idmapped like bind mounts
+            arguments.extend(["-v".to_string(), format!("{volume}:{container_dir}:U")]);
+        }
+
+        if let Some(hosts_file) = &self.config.hosts_file {
+            match fs::canonicalize(hosts_file) {
+                Ok(path) => {
+                    arguments.extend([
+                        "--mount".to_string(),
+                        format!(
+                            "type=bind,source={},destination=/etc/hosts,ro=true",
+                            path.to_str().unwrap()
+                        ),
+                    ]);
+                }
+                Err(_) => {
+                    return Err(SeaboxError::InvalidArgument(format!(
+                        "Hosts file '{hosts_file}' does not exist"
+                    )));
+                }
+            }
+        }
+
+        arguments.extend(self.resolv_conf_arguments(network)?);
+
+        if let Some(pid) = &self.config.pid {
+            self.validate_pid_namespace(pid, dry_run)?;
+            arguments.extend(["--pid".to_string(), pid.to_string()]);
+        }
+
+        if let Some(tz) = &self.config.tz {
+            arguments.extend(timezone_arguments(tz)?);
+        }
+
+        if let Some(rcfile) = &self.config.shell_rcfile {
+            let Ok(path) = fs::canonicalize(rcfile) else {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Shell rc file '{rcfile}' does not exist"
+                )));
+            };
+
+            arguments.extend([
+                "--mount".to_string(),
+                format!(
+                    "type=bind,source={},destination={SHELL_RCFILE_PATH},ro=true",
+                    path.to_str().unwrap()
+                ),
+            ]);
+        }
+
+        // After the generated flags, so they take precedence
+        if let Some(pass_through_end) = &self.config.pass_through_end
+            && let Some(pass_through_args) = shlex::split(pass_through_end)
+        {
+            arguments.extend(pass_through_args);
+        }
+
+        if let Some(entrypoint) = &self.config.entrypoint {
+            // Joined with '=' so an empty value is passed on as a reset
+            arguments.push(format!("--entrypoint={entrypoint}"));
+        }
+
+        arguments.extend(
+            ["--name", name, image]
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+        );
+
+        Ok((
+            arguments,
+            create_user,
+            container_user_id,
+            container_user_gid,
+            image.to_string(),
+        ))
+    }
+
+    pub fn generate_container_inspect_command(&self, name: &str) -> Vec<String> {
+        self.engine_command(&["container", "inspect", name])
+    }
+
+    pub fn handle_create(&mut self, args: &CreateArgs) -> Result<()> {
+        check_nesting(args.common.force)?;
+
+        let expires = match &args.rm_after {
+            Some(duration) => {
+                let seconds = parse_duration(duration).ok_or_else(|| {
+                    SeaboxError::InvalidArgument(format!(
+                        "Invalid --rm-after '{duration}', expected a number with an s, m, h, d or w unit like 2h"
+                    ))
+                })?;
+                Some(unix_time() + seconds)
+            }
+            None => None,
+        };
+
+        if args.diff {
+            self.print_box_diff(&args.name, &args.common)?;
+        }
+
+        self.create_box(&args.name, &args.common, &args.all, expires, !args.no_enter)
+    }
+
+    pub fn handle_up(&mut self, args: &UpArgs) -> Result<()> {
+        check_nesting(false)?;
+
+        // Each box resolves its arguments like a separate create, on top of the config file and
+        // environment rather than the boxes before it
+        let initial = self.config.clone();
+        for (name, box_args) in read_up_file(&args.file)? {
+            self.config = initial.clone();
+            self.resolve_config_args_create_tmp(&box_args, args.all.verbose)?;
+
+            match self.create_box(&name, &box_args, &args.all, None, false) {
+                Err(SeaboxError::ContainerExists(_)) => {
+                    if !self.quiet {
+                        eprintln!("Box '{name}' already exists, leaving it as it is");
+                    }
+                }
+                result => result?,
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_down(&mut self, args: &DownArgs) -> Result<()> {
+        let mut names = vec![];
+
+        // Boxes may depend on the ones defined before them, so remove them last
+        for (name, _) in read_up_file(&args.file)?.into_iter().rev() {
+            if args.all.dry_run || self.box_exists(&name)? {
+                names.push(name);
+            } else if !self.quiet {
+                eprintln!("Box '{name}' doesn't exist, skipping it");
+            }
+        }
+
+        self.handle_remove(&RemoveArgs {
+            names,
+            volumes: args.volumes,
+            remove_image: false,
+            all: args.all.clone(),
+        })
+    }
+
+    fn box_exists(&mut self, name: &str) -> Result<bool> {
+        self.use_box_engine_mode(name)?;
+
+        match self.inspect_container(name) {
+            Ok(_) => Ok(true),
+            Err(SeaboxError::ContainerNotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Configuration an existing box was created with, recorded or reconstructed from the container.
+    /// A reconstructed config goes through the preset and spec of `args`, like the one it's compared to
+    fn existing_box_config(
+        &mut self,
+        name: &str,
+        args: &CreateAndTempSharedArgs,
+    ) -> Result<Option<Config>> {
+        if let Some(lock) = self.read_box_lock(name)? {
+            return Ok(Some(lock.config));
+        }
+
+        let info = match self.inspect_container(name) {
+            Ok(info) => info,
+            Err(SeaboxError::ContainerNotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        // Resolve the recovered arguments on top of the config file, like a new invocation.
+        // A spec read from stdin is already used up
+        let recovered = CreateAndTempSharedArgs {
+            preset: args.preset.clone(),
+            spec: args.spec.clone().filter(|x| x != "-"),
+            ..creation_args_from_inspect(&info)
+        };
+        let wanted = std::mem::replace(
+            &mut self.config,
+            create_config(&self.parsed_config_file.base, None)?,
+        );
+        let resolved = self.resolve_config_layers(&recovered, false);
+        let existing = std::mem::replace(&mut self.config, wanted);
+        resolved?;
+
+        Ok(Some(existing))
+    }
+
+    /// Print how the resolved configuration differs from that of the existing box
+    fn print_box_diff(&mut self, name: &str, args: &CreateAndTempSharedArgs) -> Result<()> {
+        let Some(existing) = self.existing_box_config(name, args)? else {
+            println!("'{name}' doesn't exist yet, nothing to compare");
+            return Ok(());
+        };
+
+        let (Ok(serde_json::Value::Object(before)), Ok(serde_json::Value::Object(after))) = (
+            serde_json::to_value(&existing),
+            serde_json::to_value(&self.config),
+        ) else {
+            return Err(SeaboxError::Parse(
+                "Failed to serialize the configuration".to_string(),
+            ));
+        };
+
+        let changes: Vec<String> = after
+            .iter()
+            .filter(|(key, value)| before.get(*key) != Some(value))
+            .map(|(key, value)| {
+                let previous = before.get(key).unwrap_or(&serde_json::Value::Null);
+                format!("  {key}: {previous} -> {value}")
+            })
+            .collect();
+
+        if changes.is_empty() {
+            println!("No changes to '{name}'");
+        } else {
+            println!("Changes to '{name}':");
+            for change in changes {
+                println!("{change}");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_box(
+        &mut self,
+        name: &str,
+        args: &CreateAndTempSharedArgs,
+        all: &AllCommandArgs,
+        expires: Option<u64>,
+        enter: bool,
+    ) -> Result<()> {
+        let shell = args.shell.clone();
+
+        let container_inspect_command = self.generate_container_inspect_command(name);
+
+        if all.dry_run {
+            self.print_command("inspect", container_inspect_command.clone());
+        }
+
+        self.build_image(name, args, all.dry_run)?;
+
+        let (
+            mut create_container_command,
+            create_user,
+            container_user_id,
+            _container_user_gid,
+            image,
+        ) = self.generate_create_container_command(
+            self.resolve_image(all.dry_run, all.verbose, args.allow_any_image)?,
+            name,
+            self.config.root,
+            false,
+            self.config.pass_through.clone(),
+            self.config.directory.clone(),
+            self.config.volume.clone(),
+            args.volume.as_deref().unwrap_or_default(),
+            self.config.pull,
+            all.dry_run,
+        )?;
+
+        create_container_command.push("/bin/sh".to_string());
+
+        // Root boxes have no user to set up, so their init script is never read
+        let setup_command = if self.config.root {
+            None
+        } else {
+            let script = create_initial_enter_script(
+                &self.init_script_template()?,
+                create_user,
+                NEW_USER_USERNAME,
+                container_user_id,
+                self.config.unsafe_setup_passwordless_sudo,
+                self.config.no_password,
+                self.config.install_sudo,
+                shell.clone(),
+                all.verbose,
+                true,
+            );
+
+            Some(self.generate_container_enter_command(
+                "root",
+                name,
+                vec!["/bin/sh".to_string(), "-c".to_string(), script],
+                Some("/"),
+                &[],
+            ))
+        };
+
+        if all.dry_run {
+            self.check_secrets(true)?;
+            self.ensure_cache_volumes(true)?;
+            self.print_command("create", create_container_command);
+            if let Some(setup_command) = setup_command {
+                self.print_command("setup", setup_command);
+            }
+            return Ok(());
+        }
+
+        let result = self.status_quiet(&container_inspect_command)?;
+
+        if let Some(0) = result.code() {
+            return Err(SeaboxError::ContainerExists(name.to_string()));
+        }
+
+        // Only once the box is known to be new, so a name clash doesn't leave volumes behind
+        self.check_secrets(false)?;
+        self.ensure_cache_volumes(false)?;
+
+        self.warn_privileged(&format!("'{name}'"), false);
+
+        let (create_process_result, stderr) =
+            self.status_capture_stderr(&create_container_command)?;
+
+        if !create_process_result.success() {
+            if !self.config.no_idmap && is_idmap_error(&stderr) {
+                if self.config.auto_fallback {
+                    eprintln!("Warning: idmapped mounts are not supported, retrying without idmap");
+
+                    // The container may have been created before failing to start
+                    let _result = self.status_quiet(
+                        &self.generate_container_delete_command(name, self.config.remove_volumes),
+                    )?;
+
+                    self.config.no_idmap = true;
+                    return self.create_box(name, args, all, expires, enter);
+                }
+
+                return Err(SeaboxError::IdmapUnsupported(stderr.trim_end().to_string()));
+            }
+
+            if let Some(runtime) = &self.config.runtime
+                && is_runtime_error(&stderr, runtime)
+            {
+                return Err(SeaboxError::CommandFailed(format!(
+                    "Failed to create container: OCI runtime '{runtime}' is not available - check that it is installed, or pass its full path to --runtime"
+                )));
+            }
+
+            return Err(SeaboxError::CommandFailed(
+                "Failed to create container".to_string(),
+            ));
+        }
+
+        let metadata = BoxInstanceFormat {
+            name: name.to_string(),
+            image_id: self.image_id(&image),
+            image,
+            rootful: !self.config.rootless,
+            expires,
+        };
+        if let Err(e) = self.write_box_metadata(&metadata) {
+            eprintln!("Warning: failed to record metadata of '{name}': {e}");
+        }
+        if let Err(e) = self.write_box_lock(name, args) {
+            eprintln!("Warning: failed to record how '{name}' was created: {e}");
+        }
+
+        // Set up the user as root and wait for it, so failures are reported before entering
+        if let Some(setup_command) = setup_command {
+            self.wait_until_running(name)?;
+
+            let result = self.status(&setup_command)?;
+
+            if !result.success() {
+                return Err(SeaboxError::CommandFailed(format!(
+                    "Failed to set up container '{name}': the init script exited with {result}"
+                )));
+            }
+        }
+
+        if !self.quiet {
+            eprintln!("Created container '{name}'");
+        }
+
+        if !enter {
+            return Ok(());
+        }
+
+        self.enter_container(
+            name,
+            None,
+            shell,
+            all.dry_run,
+            vec![],
+            None,
+            None,
+            false,
+            false,
+            &[],
+            true,
+        )
+    }
+
+    pub fn generate_image_inspect_command(&self, image: &str) -> Vec<String> {
+        self.engine_command(&["image", "inspect", image])
+    }
+
+    fn image_inspect(&self, image: &str, dry_run: bool) -> Option<String> {
+        let inspect_image_command = self.generate_image_inspect_command(image);
+
+        if dry_run {
+            self.print_command("inspect-image", inspect_image_command.clone());
+        }
+
+        let result = self.output(&inspect_image_command);
+
+        match result {
+            Ok(r) => match r.status.code() {
+                Some(0) => {
+                    let x = r.stdout.to_owned();
+                    Some(String::from_utf8_lossy(&x).to_string())
+                }
+                _ => None,
+            },
+            Err(_) => None,
+        }
+    }
+
+    /// The script that sets up the container user, read from the init_script file if one is set
+    fn init_script_template(&self) -> Result<String> {
+        let Some(path) = &self.config.init_script else {
+            return Ok(INIT_SCRIPT.to_string());
+        };
+
+        let template = fs::read_to_string(path).map_err(|e| {
+            SeaboxError::InvalidArgument(format!("Failed to read init script '{path}': {e}"))
+        })?;
+
+        let missing: Vec<&str> = INIT_SCRIPT_REQUIRED_PLACEHOLDERS
+            .iter()
+            .copied()
+            .filter(|x| !template.contains(x))
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Init script '{path}' is missing the placeholders {}",
+                missing.join(", ")
+            )));
+        }
+
+        Ok(template)
+    }
+
+    /// Id of a local image, if it exists
+    fn image_id(&self, image: &str) -> Option<String> {
+        let inspect: Vec<PodmanImageInspectFormat> =
+            serde_json::from_str(&self.image_inspect(image, false)?).ok()?;
+
+        inspect
+            .into_iter()
+            .next()
+            .map(|x| x.id)
+            .filter(|x| !x.is_empty())
+    }
+
+    pub fn generate_image_pull_command(&self, image: &str) -> Vec<String> {
+        self.engine_command(&["pull", image])
+    }
+
+    pub fn generate_image_build_command(
+        &self,
+        tag: &str,
+        args: &CreateAndTempSharedArgs,
+    ) -> Result<Vec<String>> {
+        let Some(context) = &args.build else {
+            return Err(SeaboxError::InvalidArgument(
+                "No build context given".to_string(),
+            ));
+        };
+
+        let mut command = self.engine_command(&["build", "-t", tag]);
+
+        for build_arg in &args.build_arg {
+            if !is_valid_label(build_arg) {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Invalid build argument '{build_arg}' - expected KEY=VALUE"
+                )));
+            }
+            command.extend(["--build-arg".to_string(), build_arg.to_string()]);
+        }
+
+        if let Some(target) = &args.build_target {
+            command.extend(["--target".to_string(), target.to_string()]);
+        }
+
+        command.push(context.to_string());
+        Ok(command)
+    }
+
+    /// Build the image of --build and use it for the container in place of a configured image
+    fn build_image(
+        &mut self,
+        name: &str,
+        args: &CreateAndTempSharedArgs,
+        dry_run: bool,
+    ) -> Result<()> {
+        if args.build.is_none() {
+            return Ok(());
+        }
+
+        // The FROM lines of a Containerfile can pull any image
+        if !self.config.allowed_image_prefixes.is_empty() && !args.allow_any_image {
+            return Err(SeaboxError::InvalidArgument(
+                "--build can't be checked against allowed_image_prefixes - pass --allow-any-image to build anyway".to_string(),
+            ));
+        }
+
+        let tag = format!(
+            "{BUILD_IMAGE_PREFIX}{}",
+            if name.is_empty() { "temp" } else { name }
+        );
+        let image_build_command = self.generate_image_build_command(&tag, args)?;
+
+        if dry_run {
+            self.print_command("build", image_build_command);
+        } else if !self.status(&image_build_command)?.success() {
+            return Err(SeaboxError::CommandFailed(format!(
+                "Failed to build image '{tag}'"
+            )));
+        }
+
+        self.config.image = Some(tag);
+        self.config.image_fallbacks.clear();
+        Ok(())
+    }
+
+    pub fn generate_cat_etc_password_command(&self, image: &str) -> Vec<String> {
+        self.engine_command(&["run", "--rm", "--entrypoint", "cat", image, "/etc/passwd"])
+    }
+
+    /// Home directory of the container user, read from /etc/passwd of the image
+    fn container_home(&self, image: &str, uid: i64, create_user: bool, dry_run: bool) -> String {
+        let default_home = format!("/home/{NEW_USER_USERNAME}");
+
+        if uid == 0 {
+            return "/root".to_string();
+        } else if create_user {
+            // The init script creates the user with the default home directory
+            return default_home;
+        }
+
+        let cat_etc_passwd_command = self.generate_cat_etc_password_command(image);
+
+        if dry_run {
+            self.print_command("read-passwd", cat_etc_passwd_command.clone());
+        }
+
+        let Ok(output) = self.output(&cat_etc_passwd_command) else {
+            return default_home;
+        };
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.split(':').collect::<Vec<&str>>())
+            .find(|values| values.len() >= 6 && values[2].parse::<i64>() == Ok(uid))
+            .map(|values| values[5].to_string())
+            .filter(|home| home.starts_with('/'))
+            .unwrap_or(default_home)
+    }
+
+    fn determine_container_uid_gid(
+        &self,
+        image: &str,
+        dry_run: bool,
+    ) -> Result<Option<(i64, i64)>> {
+        // Dry runs print the commands instead
+        let show_progress = !self.quiet && !dry_run;
+
+        let mut progress = Progress::show(&format!("Resolving image {image}..."), show_progress);
+
+        let result = {
+            match self.image_inspect(image, dry_run) {
+                Some(x) => {
+                    if dry_run {
+                        eprintln!("Image '{image}' is present locally");
+                    }
+                    x
+                }
+                None => {
+                    // The pull shows its own progress
+                    progress.finish();
+
+                    let image_pull_command = self.generate_image_pull_command(image);
+
+                    if dry_run {
+                        self.print_command("pull", image_pull_command);
+                        // Probing the image for users would pull it, so plan with a new user instead
+                        eprintln!(
+                            "Image '{image}' is not present locally and would be pulled - its users can't be read without pulling it"
+                        );
+                        return Ok(None);
+                    }
+
+                    let pull = self.status(&image_pull_command)?;
+
+                    if !pull.success() {
+                        return Err(SeaboxError::ImageNotFound(image.to_string()));
+                    }
+
+                    self.image_inspect(image, dry_run)
+                        .ok_or(SeaboxError::ImageNotFound(image.to_string()))?
+                }
+            }
+        };
+
+        progress.finish();
+
+        let inspect: Vec<PodmanImageInspectFormat> =
+            serde_json::from_str(&result).map_err(|e| {
+                SeaboxError::Parse(format!("Failed to parse image inspect output: {e}"))
+            })?;
+
+        if let Some(element) = inspect.first()
+            && let Some(map) = &element.labels
+        {
+            // Images committed from a seabox container carry the ids resolved on creation
+            if let Some(uid) = map.get(RESOLVED_UID_LABEL)
+                && let Some(gid) = map.get(RESOLVED_GID_LABEL)
+                && let Ok(uid) = uid.parse::<i64>()
+                && let Ok(gid) = gid.parse::<i64>()
+            {
+                return Ok(Some((uid, gid)));
+            }
+
+            if let Some(uid) = map.get("SEABOX_USER_ID") {
+                let uid: i64 = uid.parse().map_err(|_| {
+                    SeaboxError::Parse(format!("Invalid SEABOX_USER_ID label on image: '{uid}'"))
+                })?;
+                return Ok(Some((uid, uid)));
+            }
+        }
+
+        let cat_etc_passwd_command = self.generate_cat_etc_password_command(image);
+
+        if dry_run {
+            self.print_command("read-passwd", cat_etc_passwd_command.clone());
+        }
+
+        let _progress =
+            Progress::show(&format!("Reading users of image {image}..."), show_progress);
+        let ect_passwd = self.output(&cat_etc_passwd_command);
+
+        if let Ok(output) = ect_passwd {
+            let mut user_info: Vec<(&str, i64, i64)> = vec![];
+
+            let string = String::from_utf8_lossy(&output.stdout);
+
+            for line in string.lines() {
+                let values: Vec<&str> = line.split(":").collect();
+                if values.len() < 4 {
+                    continue;
+                }
+                let username = values[0];
+
+                // Skip malformed entries rather than failing the whole scan
+                let (Ok(uid), Ok(gid)) = (values[2].parse::<i64>(), values[3].parse::<i64>())
+                else {
+                    continue;
+                };
+
+                user_info.push((username, uid, gid))
+            }
+
+            let mut user_info: Vec<_> = user_info
+                .iter()
+                .filter(|x| x.1 >= 1000 && x.1 < 2000)
+                .collect();
+            user_info.sort_by_key(|k| k.1);
+
+            if let Some(x) = user_info.last() {
+                return Ok(Some((x.1, x.2)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn generate_container_passwd_command(&self, name: &str) -> Vec<String> {
+        self.engine_command(&["exec", name, "cat", "/etc/passwd"])
+    }
+
+    /// Fail early with the users of the container if a user given by name doesn't exist in it.
+    /// Numeric ids pass through, and so does everything if /etc/passwd can't be read.
+    fn check_container_user(&self, name: &str, user: &str) -> Result<()> {
+        if !is_user_name(user) {
+            return Ok(());
+        }
+        let username = user.split(':').next().unwrap_or(user);
+
+        let Ok(result) = self.output(&self.generate_container_passwd_command(name)) else {
+            return Ok(());
+        };
+        if !result.status.success() {
+            return Ok(());
+        }
+
+        let passwd = String::from_utf8_lossy(&result.stdout);
+        let users: Vec<(&str, u32)> = passwd
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(':').collect();
+                Some((*fields.first()?, fields.get(2)?.parse().ok()?))
+            })
+            .collect();
+
+        if users.iter().any(|(x, _)| *x == username) {
+            return Ok(());
+        }
+
+        // System users are rarely what was meant
+        let available: Vec<&str> = users
+            .iter()
+            .filter(|(_, uid)| *uid == 0 || (1000..65534).contains(uid))
+            .map(|(x, _)| *x)
+            .collect();
+
+        Err(SeaboxError::InvalidArgument(format!(
+            "User '{username}' doesn't exist in container '{name}' - available users: {}",
+            available.join(", ")
+        )))
+    }
+
+    pub fn handle_enter(&mut self, args: &EnterArgs) -> Result<()> {
+        check_nesting(args.force)?;
+        self.use_box_engine_mode(&args.name)?;
+
+        if let Some(keys) = &args.detach_keys {
+            self.config.detach_keys = Some(keys.to_string());
+        }
+
+        let user = if args.root {
+            Some("root".to_string())
+        } else {
+            args.user.clone()
+        };
+
+        if let Some(user) = &user
+            && !is_valid_user(user)
+        {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Invalid user '{user}' - expected 'name', 'uid', 'uid:gid', 'uid:' or 'name:group'"
+            )));
+        }
+
+        if let Some(workdir) = &args.workdir
+            && !workdir.starts_with('/')
+        {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Invalid working directory '{workdir}' - expected an absolute path"
+            )));
+        }
+
+        if args.replace_on_image_change {
+            self.replace_on_image_change(&args.name, args.yes, &args.all)?;
+        }
+
+        self.enter_container(
+            &args.name,
+            user,
+            args.shell.clone(),
+            args.all.dry_run,
+            vec![],
+            args.workdir.clone(),
+            args.path.clone(),
+            args.no_workdir,
+            args.mkdir,
+            &args.env,
+            !args.no_start,
+        )
+    }
+
+    /// Recreate a box whose image changed since it was created, after asking unless `yes` is set
+    fn replace_on_image_change(
+        &mut self,
+        name: &str,
+        yes: bool,
+        all: &AllCommandArgs,
+    ) -> Result<()> {
+        let Some(metadata) = self.read_box_metadata(name)? else {
+            return Ok(());
+        };
+        let Some(created_id) = &metadata.image_id else {
+            return Ok(());
+        };
+
+        if all.dry_run {
+            self.print_command(
+                "inspect-image",
+                self.generate_image_inspect_command(&metadata.image),
+            );
+        }
+
+        let Some(current_id) = self.image_id(&metadata.image) else {
+            return Ok(());
+        };
+        if current_id == *created_id {
+            return Ok(());
+        }
+
+        let question = format!(
+            "The image '{}' of '{name}' changed since it was created - recreate '{name}' on the new image? Changes made outside of mounted directories will be lost [y/N] ",
+            metadata.image
+        );
+
+        if !(yes || all.dry_run || confirm(&question)) {
+            return Ok(());
+        }
+
+        // Recreating switches to the recorded config, entering goes on with this one
+        let enter_config = self.config.clone();
+        let result = self.recreate_box(name, all, false);
+        self.config = enter_config;
+        result
+    }
+
+    /// Create a working directory as root, owned by the user about to enter it
+    pub fn generate_workdir_mkdir_command(
+        &self,
+        name: &str,
+        workdir: &str,
+        user: &str,
+    ) -> Vec<String> {
+        // Users given as "uid:" leave the group to chown, like a bare user name
+        let owner = user.trim_end_matches(':');
+
+        self.engine_command(&[
+            "exec",
+            "--user",
+            "root",
+            name,
+            "/bin/sh",
+            "-c",
+            r#"mkdir -p "$1" && chown "$2" "$1""#,
+            "sh",
+            workdir,
+            owner,
+        ])
+    }
+
+    pub fn generate_container_enter_command(
+        &self,
+        user: &str,
+        name: &str,
+        exec_command: Vec<String>,
+        workdir: Option<&str>,
+        env: &[String],
+    ) -> Vec<String> {
+        let mut command: Vec<String> = match workdir {
+            Some(workdir) => self.engine_command(&["exec", "-it", "-w", workdir, "--user", user]),
+            // The container's own working directory is used
+            None => self.engine_command(&["exec", "-it", "--user", user]),
+        };
+
+        if let Some(keys) = &self.config.detach_keys {
+            // Joined with '=' so an empty value is passed on to disable detaching
+            command.push(format!("--detach-keys={keys}"));
+        }
+
+        for var in env {
+            command.extend(["-e".to_string(), var.to_string()]);
+        }
+
+        command.push(name.to_string());
+        command.extend(exec_command);
+
+        command
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn enter_container(
+        &self,
+        name: &str,
+        username: Option<String>,
+        shell: Option<String>,
+        dry_run: bool,
+        append_args: Vec<String>,
+        workdir: Option<String>,
+        subdir: Option<String>,
+        no_workdir: bool,
+        mkdir: bool,
+        env: &[String],
+        start: bool,
+    ) -> Result<()> {
+        let env = env
+            .iter()
+            .map(|x| resolve_session_env(x))
+            .collect::<Result<Vec<String>>>()?;
+
+        let shell_command: Vec<String> = {
+            if !append_args.is_empty() {
+                append_args
+            } else if let Some(s) = &shell {
+                vec![s.to_string()]
+            } else {
+                DEFAULT_SHELL.iter().map(|x| x.to_string()).collect()
+            }
+        };
+
+        let container_inspect_command = self.generate_container_inspect_command(name);
+        let container_start_command = self.generate_container_start_command(name);
+
+        let info = self.inspect_container(name)?;
+
+        // Land in the subdirectory of the mount corresponding to the current directory
+        let workdir: String = {
+            let target = mount_target(&info);
+
+            if let Some(w) = workdir {
+                w
+            } else if let Some(subdir) = subdir {
+                format!("{target}/{}", subdir.trim_matches('/'))
+            } else if let Some(mount) = info
+                .mounts
+                .iter()
+                .find(|m| m.destination.trim_end_matches('/') == target)
+            {
+                mount_relative_workdir(&mount.source, target)
+            } else {
+                "".to_string()
+            }
+        };
+
+        let user_check_command = username
+            .as_deref()
+            .is_some_and(is_user_name)
+            .then(|| self.generate_container_passwd_command(name));
+
+        let user = match username {
+            Some(x) => x,
+            _ => default_enter_user(&info),
+        };
+
+        let mkdir_command =
+            mkdir.then(|| self.generate_workdir_mkdir_command(name, &workdir, &user));
+
+        let container_enter_command = self.generate_container_enter_command(
+            &user,
+            name,
+            shell_command,
+            (!no_workdir).then_some(workdir.as_str()),
+            &env,
+        );
+
+        if dry_run {
+            self.print_command("inspect", container_inspect_command);
+            self.print_command("start", container_start_command);
+            if let Some(user_check_command) = user_check_command {
+                self.print_command("list-users", user_check_command);
+            }
+            if let Some(mkdir_command) = mkdir_command {
+                self.print_command("mkdir", mkdir_command);
+            }
+            self.print_command("enter", container_enter_command);
+            return Ok(());
+        }
+
+        if !info.state.running {
+            if !start {
+                return Err(SeaboxError::CommandFailed(format!(
+                    "Container '{name}' isn't running - start it with 'seabox restart {name}', or enter without --no-start"
+                )));
+            }
+
+            if !self.quiet {
+                eprintln!("Starting stopped box '{name}'...");
+            }
+
+            let result = self.status(&container_start_command)?;
+
+            if !result.success() {
+                return Err(SeaboxError::CommandFailed(
+                    "Failed to start container".to_string(),
+                ));
+            }
+
+            self.wait_until_running(name)?;
+        }
+
+        if user_check_command.is_some() {
+            self.check_container_user(name, &user)?;
+        }
+
+        if let Some(mkdir_command) = mkdir_command {
+            let result = self.output(&mkdir_command)?;
+            if !result.status.success() {
+                return Err(SeaboxError::CommandFailed(format!(
+                    "Failed to create working directory '{workdir}': {}",
+                    String::from_utf8_lossy(&result.stderr).trim_end()
+                )));
+            }
+        }
+
+        self.exec(&container_enter_command)
+    }
+
+    /// Wait for a container that was just started to be running, which can take a moment on
+    /// slow storage, so exec doesn't fail with "container not running"
+    fn wait_until_running(&self, name: &str) -> Result<()> {
+        let timeout = std::time::Duration::from_secs(self.config.start_timeout);
+        let deadline = std::time::Instant::now() + timeout;
+
+        loop {
+            if self.inspect_container(name)?.state.running {
+                return Ok(());
+            }
+
+            if std::time::Instant::now() >= deadline {
+                return Err(SeaboxError::CommandFailed(format!(
+                    "Container '{name}' was started, but isn't running after {}s. Raise start_timeout in the config to wait longer",
+                    self.config.start_timeout
+                )));
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(250));
+        }
+    }
+
+    /// Inspect a container, retrying briefly if podman doesn't report it yet, as can happen
+    /// right after it was created
+    fn inspect_container(&self, name: &str) -> Result<PodmanContainerInspectFormat> {
+        const ATTEMPTS: u32 = 5;
+
+        let container_inspect_command = self.generate_container_inspect_command(name);
+
+        for attempt in 1..=ATTEMPTS {
+            let result = self.output(&container_inspect_command)?;
+
+            if !result.status.success() {
+                return Err(SeaboxError::ContainerNotFound {
+                    name: name.to_string(),
+                    detail: String::from_utf8_lossy(&result.stderr)
+                        .trim_end()
+                        .to_string(),
+                    suggestions: self.similar_box_names(name),
+                });
+            }
+
+            let stdout_text = String::from_utf8_lossy(&result.stdout);
+            let info: Option<Vec<PodmanContainerInspectFormat>> = match stdout_text.trim() {
+                "" | "null" => None,
+                x => serde_json::from_str(x).map_err(|e| {
+                    SeaboxError::Parse(format!("Failed to parse container inspect output: {e}"))
+                })?,
+            };
+
+            if let Some(info) = info.and_then(|x| x.into_iter().next()) {
+                return Ok(info);
+            }
+
+            if attempt < ATTEMPTS {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+        }
+
+        Err(SeaboxError::CommandFailed(format!(
+            "Container '{name}' was created, but isn't inspectable yet - try again in a moment"
+        )))
+    }
+
+    pub fn handle_remove(&mut self, args: &RemoveArgs) -> Result<()> {
+        for name in &args.names {
+            self.use_box_engine_mode(name)?;
+
+            let stop_container_command = self.generate_container_stop_command(name);
+            let delete_container_command = self.generate_container_delete_command(
+                name,
+                args.volumes || self.config.remove_volumes,
+            );
+
+            // Looked up before the container and its metadata are gone
+            let image = if args.remove_image {
+                self.box_image(name)
+            } else {
+                None
+            };
+
+            if args.all.dry_run {
+                self.print_command("stop", stop_container_command);
+                self.print_command("remove", delete_container_command);
+                if let Some(image) = &image {
+                    self.print_command(
+                        "list-image-users",
+                        self.generate_image_users_command(image),
+                    );
+                    self.print_command("remove-image", self.generate_image_remove_command(image));
+                }
+            } else {
+                println!("Deleting container {name}");
+
+                let _result = self.status_quiet(&stop_container_command)?;
+                let result = self.status(&delete_container_command)?;
+
+                if result.success() {
+                    self.remove_box_metadata(name);
+
+                    if let Some(image) = &image {
+                        self.remove_unused_image(image)?;
+                    }
+                } else if !self
+                    .status_quiet(&self.generate_container_inspect_command(name))?
+                    .success()
+                {
+                    // Only a missing box can be a typo, others failed for another reason
+                    let suggestions = self.similar_box_names(name);
+                    if !suggestions.is_empty() {
+                        let suggestions: Vec<String> =
+                            suggestions.iter().map(|x| format!("'{x}'")).collect();
+                        eprintln!("Did you mean {}?", suggestions.join(" or "));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_temp(&mut self, args: &TempArgs) -> Result<()> {
+        check_nesting(args.common.force)?;
+        let script = match args.script.as_deref() {
+            Some("-") => {
+                let mut script = String::new();
+                std::io::stdin().read_to_string(&mut script).map_err(|e| {
+                    SeaboxError::InvalidArgument(format!("Failed to read script from stdin: {e}"))
+                })?;
+                Some(script)
+            }
+            Some(path) => Some(fs::read_to_string(path).map_err(|e| {
+                SeaboxError::InvalidArgument(format!("Failed to read script '{path}': {e}"))
+            })?),
+            // The configured command runs like a script, as the user once it is set up
+            None if args.common.shell.is_none() && !self.config.temp_command.is_empty() => Some(
+                shlex::try_join(self.config.temp_command.iter().map(|x| x.as_str()))
+                    .map_err(|e| SeaboxError::Config(format!("Invalid temp_command: {e}")))?,
+            ),
+            None => None,
+        };
+
+        if !args.all.dry_run {
+            let subject = match &args.name {
+                Some(name) => format!("'{name}'"),
+                None => "this temp box".to_string(),
+            };
+            self.warn_privileged(&subject, true);
+        }
+
+        self.run_temp(args, script.as_deref())
+    }
+
+    /// Warn that a box is privileged, once per invocation and unless that's suppressed
+    fn warn_privileged(&mut self, subject: &str, temp: bool) {
+        if self.privileged_warned
+            || !self.config.privileged.unwrap_or(!temp)
+            || self.config.suppress_privileged_warning
+            || self.quiet
+            || !std::io::stderr().is_terminal()
+        {
+            return;
+        }
+
+        self.privileged_warned = true;
+        eprintln!(
+            "Warning: {subject} is privileged - root in it has all capabilities and access to host devices. Pass --privileged=false to drop that, or --no-privileged-warning to hide this"
+        );
+    }
+
+    fn run_temp(&mut self, args: &TempArgs, script: Option<&str>) -> Result<()> {
+        let shell: Vec<String> = {
+            if let Some(script) = script {
+                vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()]
+            } else if let Some(s) = &args.common.shell {
+                vec![s.to_string()]
+            } else {
+                DEFAULT_SHELL.iter().map(|x| x.to_string()).collect()
+            }
+        };
+
+        self.build_image(
+            args.name.as_deref().unwrap_or(""),
+            &args.common,
+            args.all.dry_run,
+        )?;
+
+        let (
+            mut create_container_command,
+            create_user,
+            container_user_id,
+            _container_user_gid,
+            _image,
+        ) = self.generate_create_container_command(
+            self.resolve_image(
+                args.all.dry_run,
+                args.all.verbose,
+                args.common.allow_any_image,
+            )?,
+            args.name.as_deref().unwrap_or(""),
+            self.config.root,
+            true,
+            self.config.pass_through.clone(),
+            self.config.directory.clone(),
+            self.config.volume.clone(),
+            args.common.volume.as_deref().unwrap_or_default(),
+            self.config.pull,
+            args.all.dry_run,
+        )?;
+
+        let user_command = {
+            if !self.config.root {
+                let mut command = vec![
+                    "/bin/sh".to_string(),
+                    "-c".to_string(),
+                    create_initial_enter_script(
+                        &self.init_script_template()?,
+                        create_user,
+                        NEW_USER_USERNAME,
+                        container_user_id,
+                        self.config.unsafe_setup_passwordless_sudo,
+                        // Nobody is around to type a password for a script
+                        self.config.no_password || script.is_some(),
+                        self.config.install_sudo,
+                        args.common.shell.clone(),
+                        args.all.verbose,
+                        false,
+                    ),
+                ];
+
+                // The init script runs its first argument as the user instead of a login shell
+                if let Some(script) = script {
+                    command.extend([SEABOX_NAME.to_string(), script.to_string()]);
+                }
+
+                command
+            } else {
+                shell
+            }
+        };
+
+        create_container_command.extend(user_command);
+
+        // A script piped through stdin leaves no terminal to attach to
+        if script.is_some() && !std::io::stdin().is_terminal() {
+            for arg in create_container_command.iter_mut() {
+                if arg == "-it" {
+                    *arg = "-i".to_string();
+                }
+            }
+        }
+
+        self.check_secrets(args.all.dry_run)?;
+        self.ensure_cache_volumes(args.all.dry_run)?;
+
+        if args.all.dry_run {
+            self.print_command("run", create_container_command);
+            return Ok(());
+        }
+
+        let result = {
+            // Outlive an interrupt, which podman forwards to the container, to clean up after it
+            let _guard = runner::InterruptGuard::install();
+            self.status(&create_container_command)?
+        };
+
+        if let Some(name) = &args.name {
+            self.remove_lingering_temp(name)?;
+        }
+
+        // The session's stderr goes straight to the terminal, so an idmap failure can only be
+        // told apart by podman's exit code for a container that couldn't start
+        let uses_idmap = create_container_command
+            .iter()
+            .any(|x| x.contains("idmap="));
+        if result.code() == Some(126) && !self.config.no_idmap && uses_idmap {
+            if self.config.auto_fallback {
+                eprintln!("Warning: the box failed to start, retrying without idmap");
+                self.config.no_idmap = true;
+                return self.run_temp(args, script);
+            }
+
+            eprintln!(
+                "Hint: if idmapped mounts are not supported here, retry with --no-idmap or --auto-fallback"
+            );
+        }
+
+        if script.is_some() && !result.success() {
+            return Err(SeaboxError::ScriptFailed(result.code().unwrap_or(1)));
+        }
+
+        Ok(())
+    }
+
+    /// Image a box was created from, from its metadata or the container itself
+    fn box_image(&self, name: &str) -> Option<String> {
+        if let Ok(Some(metadata)) = self.read_box_metadata(name) {
+            return Some(metadata.image);
+        }
+
+        match self.inspect_container(name) {
+            Ok(info) => Some(info.image_name),
+            Err(_) => {
+                eprintln!("Warning: can't determine the image of '{name}', not removing it");
+                None
+            }
+        }
+    }
+
+    pub fn generate_image_users_command(&self, image: &str) -> Vec<String> {
+        self.engine_command(&[
+            "ps",
+            "--all",
+            "--filter",
+            &format!("ancestor={image}"),
+            "--format",
+            "{{.Names}}",
+        ])
+    }
+
+    pub fn generate_image_remove_command(&self, image: &str) -> Vec<String> {
+        self.engine_command(&["rmi", image])
+    }
+
+    /// Remove an image, unless containers still use it
+    fn remove_unused_image(&self, image: &str) -> Result<()> {
+        let users = self.output(&self.generate_image_users_command(image))?;
+        let users: Vec<String> = String::from_utf8_lossy(&users.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(String::from)
+            .collect();
+
+        if !users.is_empty() {
+            eprintln!(
+                "Warning: not removing image '{image}', it is still used by {}",
+                users.join(", ")
+            );
+            return Ok(());
+        }
+
+        println!("Deleting image {image}");
+        if !self
+            .status(&self.generate_image_remove_command(image))?
+            .success()
+        {
+            eprintln!("Warning: failed to remove image '{image}'");
+        }
+
+        Ok(())
+    }
+
+    /// Remove a named temp container that --rm didn't remove, such as when it failed to start
+    fn remove_lingering_temp(&self, name: &str) -> Result<()> {
+        let container_inspect_command = self.generate_container_inspect_command(name);
+
+        if !self.status_quiet(&container_inspect_command)?.success() {
+            return Ok(());
+        }
+
+        eprintln!("Warning: temporary container '{name}' was not removed on exit, removing it");
+
+        // Stop it with its grace period first, so it doesn't die mid-write to a mounted file
+        let stop_container_command = self.generate_container_stop_command(name);
+        if !self.status_quiet(&stop_container_command)?.success() {
+            eprintln!("Warning: failed to stop temporary container '{name}'");
+        }
+
+        let delete_container_command =
+            self.generate_container_delete_command(name, self.config.remove_volumes);
+        if !self.status_quiet(&delete_container_command)?.success() {
+            eprintln!("Warning: failed to remove temporary container '{name}'");
+        }
+
+        Ok(())
+    }
+
+    pub fn generate_volume_exists_command(&self, volume: &str) -> Vec<String> {
+        self.engine_command(&["volume", "exists", volume])
+    }
+
+    pub fn generate_cache_volume_create_command(&self, volume: &str) -> Vec<String> {
+        self.engine_command(&[
+            "volume",
+            "create",
+            "--label",
+            &format!("{CACHE_LABEL}=true"),
+            volume,
+        ])
+    }
+
+    /// Create the cache volumes of the config that don't exist yet
+    fn ensure_cache_volumes(&self, dry_run: bool) -> Result<()> {
+        for cache in &self.config.mount_cache {
+            let (volume, _) = parse_cache_spec(cache)?;
+
+            let volume_exists_command = self.generate_volume_exists_command(&volume);
+            let volume_create_command = self.generate_cache_volume_create_command(&volume);
+
+            if dry_run {
+                self.print_command("volume-exists", volume_exists_command);
+                self.print_command("volume-create", volume_create_command);
+                continue;
+            }
+
+            if self.status_quiet(&volume_exists_command)?.success() {
+                continue;
+            }
+
+            if !self.status_quiet(&volume_create_command)?.success() {
+                return Err(SeaboxError::CommandFailed(format!(
+                    "Failed to create cache volume '{volume}'"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn generate_secret_inspect_command(&self, secret: &str) -> Vec<String> {
+        self.engine_command(&["secret", "inspect", secret])
+    }
+
+    /// Check the secrets of the config exist, so create fails with a clear error if one doesn't
+    fn check_secrets(&self, dry_run: bool) -> Result<()> {
+        for secret in &self.config.secret {
+            let name = secret_name(secret);
+            let secret_inspect_command = self.generate_secret_inspect_command(name);
+
+            if dry_run {
+                self.print_command("inspect-secret", secret_inspect_command);
+                continue;
+            }
+
+            if !self.status_quiet(&secret_inspect_command)?.success() {
+                return Err(SeaboxError::InvalidArgument(format!(
+                    "Secret '{name}' doesn't exist - create it first with 'podman secret create {name} FILE'"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn generate_list_cache_volumes_command(&self) -> Vec<String> {
+        self.engine_command(&[
+            "volume",
+            "ls",
+            "--filter",
+            &format!("label={CACHE_LABEL}=true"),
+            "--format",
+            "{{.Name}}",
+        ])
+    }
+
+    pub fn generate_volume_remove_command(&self, volume: &str) -> Vec<String> {
+        self.engine_command(&["volume", "rm", volume])
+    }
+
+    pub fn generate_engine_version_command(&self) -> Vec<String> {
+        self.engine_command(&["version", "--format", "{{.Client.Version}}"])
+    }
+
+    pub fn handle_doctor(&self, args: &DoctorArgs) -> Result<()> {
+        let engine_version_command = self.generate_engine_version_command();
+        if args.all.dry_run {
+            self.print_command("version", engine_version_command);
+            return Ok(());
+        }
+
+        let mut checks: Vec<(&str, &str, String)> = vec![];
+
+        checks.push(match self.output(&engine_version_command) {
+            Ok(result) if result.status.success() => (
+                "engine",
+                "ok",
+                format!("podman {}", String::from_utf8_lossy(&result.stdout).trim()),
+            ),
+            Ok(result) => (
+                "engine",
+                "fail",
+                String::from_utf8_lossy(&result.stderr)
+                    .trim_end()
+                    .to_string(),
+            ),
+            Err(e) => ("engine", "fail", e.to_string()),
+        });
+
+        checks.push(if self.config.no_idmap {
+            ("idmap", "ok", "disabled with no_idmap".to_string())
+        } else {
+            // Idmapped mounts need Linux 5.12 or later, filesystems can still lack support
+            let release = fs::read_to_string("/proc/sys/kernel/osrelease").unwrap_or_default();
+            let mut version = release
+                .trim()
+                .split(['.', '-'])
+                .map(|x| x.parse::<u32>().ok());
+            match (version.next().flatten(), version.next().flatten()) {
+                (Some(major), Some(minor)) if (major, minor) >= (5, 12) => (
+                    "idmap",
+                    "ok",
+                    format!("Linux {} supports idmapped mounts", release.trim()),
+                ),
+                (Some(_), Some(_)) => (
+                    "idmap",
+                    "fail",
+                    format!(
+                        "Linux {} is older than 5.12 and can't idmap mounts - set no_idmap = true",
+                        release.trim()
+                    ),
+                ),
+                _ => (
+                    "idmap",
+                    "warn",
+                    "Couldn't read the kernel version".to_string(),
+                ),
+            }
+        });
+
+        checks.push(
+            if is_selinux_enforcing() && self.config.relabel.is_none() && !self.config.auto_relabel {
+                (
+                    "selinux",
+                    "warn",
+                    "SELinux is enforcing - set auto_relabel = true or pass --relabel so containers can access mounted directories".to_string(),
+                )
+            } else {
+                ("selinux", "ok", "No relabeling needed".to_string())
+            },
+        );
+
+        checks.push(match &self.config.image {
+            Some(image) => ("image", "ok", format!("Default image is '{image}'")),
+            None => (
+                "image",
+                "warn",
+                "No default image configured - pass -i to create and tmp".to_string(),
+            ),
+        });
+
+        let failed = checks
+            .iter()
+            .filter(|(_, status, _)| *status == "fail")
+            .count();
+
+        if args.json {
+            let checks: Vec<serde_json::Value> = checks
+                .iter()
+                .map(|(check, status, detail)| {
+                    serde_json::json!({ "check": check, "status": status, "detail": detail })
+                })
+                .collect();
+            let report = serde_json::json!({ "ok": failed == 0, "checks": checks });
+            let report = serde_json::to_string_pretty(&report)
+                .map_err(|e| SeaboxError::Parse(format!("Failed to serialize checks: {e}")))?;
+            println!("{report}");
+        } else {
+            for (check, status, detail) in &checks {
+                println!("[{status:^4}] {check}: {detail}");
+            }
+        }
+
+        match failed {
+            0 => Ok(()),
+            _ => Err(SeaboxError::CommandFailed(format!(
+                "{failed} of {} checks failed",
+                checks.len()
+            ))),
+        }
+    }
+
+    pub fn handle_prune(&mut self, args: &PruneArgs) -> Result<()> {
+        if !args.caches && !args.expired {
+            println!(
+                "Nothing to prune - pass --caches to remove cache volumes or --expired to remove expired containers"
+            );
+            return Ok(());
+        }
+
+        if args.expired {
+            self.prune_expired_boxes(&args.all)?;
+        }
+
+        if args.caches {
+            self.prune_cache_volumes(&args.all)?;
+        }
+
+        Ok(())
+    }
+
+    /// Names of boxes whose --rm-after expiry has passed, from their metadata
+    fn expired_box_names(&self) -> Result<Vec<String>> {
+        let dir = self.data_dir.join("boxes");
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(x) => x,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => {
+                return Err(SeaboxError::Metadata(format!(
+                    "Failed to read '{}': {e}",
+                    dir.display()
+                )));
+            }
+        };
+
+        let now = unix_time();
+        let mut names = vec![];
+
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(name) = file_name.strip_suffix(".json") else {
+                continue;
+            };
+            if name.ends_with(".lock") {
+                continue;
+            }
+
+            if let Some(metadata) = self.read_box_metadata(name)?
+                && metadata.expires.is_some_and(|expires| expires <= now)
+            {
+                names.push(metadata.name);
+            }
+        }
+
+        names.sort();
+        Ok(names)
+    }
+
+    fn prune_expired_boxes(&mut self, all: &AllCommandArgs) -> Result<()> {
+        let names = self.expired_box_names()?;
+
+        if names.is_empty() {
+            println!("No expired containers found");
+        }
+
+        let rootless = self.config.rootless;
+        let mut failed = vec![];
+
+        for name in &names {
+            self.use_box_engine_mode(name)?;
+
+            let stop_container_command = self.generate_container_stop_command(name);
+            let delete_container_command =
+                self.generate_container_delete_command(name, self.config.remove_volumes);
+
+            if all.dry_run {
+                self.print_command("stop", stop_container_command);
+                self.print_command("remove", delete_container_command);
+            } else {
+                let _result = self.status_quiet(&stop_container_command)?;
+                let result = self.status_quiet(&delete_container_command)?;
+
+                // A container already removed outside of seabox only leaves its metadata behind
+                let exists = self
+                    .status_quiet(&self.generate_container_inspect_command(name))?
+                    .success();
+
+                if result.success() || !exists {
+                    self.remove_box_metadata(name);
+                    println!("{name}: removed");
+                } else {
+                    println!("{name}: failed to remove ({result})");
+                    failed.push(name.clone());
+                }
+            }
+
+            self.config.rootless = rootless;
+        }
+
+        summarize("remove", &names, &failed)
+    }
+
+    fn prune_cache_volumes(&self, all: &AllCommandArgs) -> Result<()> {
+        let list_volumes_command = self.generate_list_cache_volumes_command();
+
+        if all.dry_run {
+            self.print_command("list-volumes", list_volumes_command.clone());
+        }
+
+        let result = self.output(&list_volumes_command)?;
+
+        if !result.status.success() {
+            return Err(SeaboxError::CommandFailed(format!(
+                "Failed to list cache volumes: {}",
+                String::from_utf8_lossy(&result.stderr).trim_end()
+            )));
+        }
+
+        let volumes: Vec<String> = String::from_utf8_lossy(&result.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(String::from)
+            .collect();
+
+        if volumes.is_empty() {
+            println!("No cache volumes found");
+        }
+
+        let mut failed = vec![];
+
+        for volume in &volumes {
+            let volume_remove_command = self.generate_volume_remove_command(volume);
+
+            if all.dry_run {
+                self.print_command("remove-volume", volume_remove_command);
+            } else {
+                let result = self.status_quiet(&volume_remove_command)?;
+
+                if result.success() {
+                    println!("{volume}: removed");
+                } else {
+                    // Volumes still mounted by a container can't be removed
+                    println!("{volume}: failed to remove, is it in use? ({result})");
+                    failed.push(volume.clone());
+                }
+            }
+        }
+
+        summarize("remove", &volumes, &failed)
+    }
+
+    pub fn generate_list_containers_command(&self) -> Vec<String> {
+        self.engine_command(&[
+            "ps",
+            "--all",
+            "--filter",
+            &format!("label={}=true", SEABOX_NAME),
+        ])
+    }
+
+    pub fn handle_list(&self, args: &ListArgs) -> Result<()> {
+        if args.names_only {
+            if args.all.dry_run {
+                self.print_command("list", self.generate_list_container_names_command());
+                return Ok(());
+            }
+
+            for name in self.list_box_names()? {
+                println!("{name}");
+            }
+            return Ok(());
+        }
+
+        let mut list_containers_command = self.generate_list_containers_command();
+        if args.json {
+            list_containers_command.extend(["--format".to_string(), "json".to_string()]);
+        }
+
+        if args.all.dry_run {
+            self.print_command("list", list_containers_command);
+            return Ok(());
+        }
+
+        let result = self.output(&list_containers_command)?;
+        if !result.status.success() {
+            return Err(SeaboxError::CommandFailed(format!(
+                "Failed to list containers: {}",
+                String::from_utf8_lossy(&result.stderr).trim_end()
+            )));
+        }
+
+        let stdout = String::from_utf8_lossy(&result.stdout);
+
+        if args.json {
+            // Some podman versions print null, or nothing, when no containers match
+            let containers = match stdout.trim() {
+                "" | "null" => serde_json::Value::Array(vec![]),
+                x => serde_json::from_str(x).map_err(|e| {
+                    SeaboxError::Parse(format!("Failed to parse container list: {e}"))
+                })?,
+            };
+
+            let containers = serde_json::to_string_pretty(&containers)
+                .map_err(|e| SeaboxError::Parse(format!("Failed to serialize containers: {e}")))?;
+            println!("{containers}");
+        } else if stdout.lines().filter(|x| !x.trim().is_empty()).count() <= 1 {
+            // Only the header
+            eprintln!("No seabox containers found");
+        } else {
+            print!("{stdout}");
+        }
+
+        Ok(())
+    }
+
+    /// List boxes of the rootful and the rootless store, merged and annotated with their store
+    pub fn handle_list_all_stores(&mut self, args: &ListArgs) -> Result<()> {
+        let rootless = self.config.rootless;
+        let mut listed = vec![];
+
+        for (store, store_rootless) in [("rootful", false), ("rootless", true)] {
+            self.config.rootless = store_rootless;
+            let mut list_containers_command = self.generate_list_containers_command();
+            list_containers_command.extend(["--format".to_string(), "json".to_string()]);
+
+            if args.all.dry_run {
+                self.print_command("list", list_containers_command);
+                continue;
+            }
+
+            // One store being unavailable, like rootless podman missing, doesn't hide the other
+            match self.output(&list_containers_command) {
+                Ok(result) if result.status.success() => listed.push((store, result.stdout)),
+                Ok(result) => eprintln!(
+                    "Warning: failed to list containers of the {store} store: {}",
+                    String::from_utf8_lossy(&result.stderr).trim_end()
+                ),
+                Err(e) => eprintln!("Warning: failed to list containers of the {store} store: {e}"),
+            }
+        }
+
+        self.config.rootless = rootless;
+
+        if args.all.dry_run {
+            return Ok(());
+        }
+
+        if listed.is_empty() {
+            return Err(SeaboxError::CommandFailed(
+                "Failed to list containers of either store".to_string(),
+            ));
+        }
+
+        let mut containers: Vec<serde_json::Value> = vec![];
+        for (store, stdout) in listed {
+            let stdout = String::from_utf8_lossy(&stdout);
+            let store_containers: Vec<serde_json::Value> = match stdout.trim() {
+                "" | "null" => vec![],
+                x => serde_json::from_str(x).map_err(|e| {
+                    SeaboxError::Parse(format!("Failed to parse container list: {e}"))
+                })?,
+            };
+
+            for mut container in store_containers {
+                // Both commands reach the same store when seabox already runs as root
+                if container.get("Id").is_some()
+                    && containers
+                        .iter()
+                        .any(|x| x.get("Id") == container.get("Id"))
+                {
+                    continue;
+                }
+
+                if let Some(object) = container.as_object_mut() {
+                    object.insert("Store".to_string(), store.into());
+                }
+                containers.push(container);
+            }
+        }
+
+        if args.json {
+            let containers = serde_json::Value::Array(containers);
+            let containers = serde_json::to_string_pretty(&containers)
+                .map_err(|e| SeaboxError::Parse(format!("Failed to serialize containers: {e}")))?;
+            println!("{containers}");
+            return Ok(());
+        }
+
+        if args.names_only {
+            for container in &containers {
+                println!("{}", container["Names"][0].as_str().unwrap_or(""));
+            }
+            return Ok(());
+        }
+
+        if containers.is_empty() {
+            eprintln!("No seabox containers found");
+            return Ok(());
+        }
+
+        let rows: Vec<[String; 4]> = containers
+            .iter()
+            .map(|container| {
+                let field = |key: &str| container[key].as_str().unwrap_or("").to_string();
+                let name = container["Names"][0].as_str().unwrap_or("").to_string();
+                [name, field("Store"), field("Image"), field("State")]
+            })
+            .collect();
+
+        let header = ["NAME", "STORE", "IMAGE", "STATE"].map(String::from);
+        let widths: Vec<usize> = (0..4)
+            .map(|i| {
+                rows.iter()
+                    .chain(std::iter::once(&header))
+                    .map(|row| row[i].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for row in std::iter::once(&header).chain(rows.iter()) {
+            println!(
+                "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2]
+            );
+        }
+
+        Ok(())
+    }
+
+    /// List the images boxes use, grouped with the boxes using each
+    pub fn handle_list_images(&self, args: &ListArgs) -> Result<()> {
+        let mut list_containers_command = self.generate_list_containers_command();
+        list_containers_command.extend(["--format".to_string(), "json".to_string()]);
+
+        if args.all.dry_run {
+            self.print_command("list", list_containers_command);
+            return Ok(());
+        }
+
+        let result = self.output(&list_containers_command)?;
+        if !result.status.success() {
+            return Err(SeaboxError::CommandFailed(format!(
+                "Failed to list containers: {}",
+                String::from_utf8_lossy(&result.stderr).trim_end()
+            )));
+        }
+
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let containers: Vec<serde_json::Value> = match stdout.trim() {
+            "" | "null" => vec![],
+            x => serde_json::from_str(x)
+                .map_err(|e| SeaboxError::Parse(format!("Failed to parse container list: {e}")))?,
+        };
+
+        let mut images: std::collections::BTreeMap<String, Vec<String>> =
+            std::collections::BTreeMap::new();
+        for container in &containers {
+            let image = container["Image"].as_str().unwrap_or("").to_string();
+            let name = container["Names"][0].as_str().unwrap_or("").to_string();
+            images.entry(image).or_default().push(name);
+        }
+
+        let images: Vec<(String, Vec<String>, Option<u64>)> = images
+            .into_iter()
+            .map(|(image, names)| {
+                let size = self
+                    .image_inspect(&image, false)
+                    .and_then(|x| serde_json::from_str::<Vec<PodmanImageInspectFormat>>(&x).ok())
+                    .and_then(|x| x.into_iter().next())
+                    .and_then(|x| x.size);
+                (image, names, size)
+            })
+            .collect();
+
+        if args.json {
+            let images: Vec<serde_json::Value> = images
+                .iter()
+                .map(|(image, names, size)| {
+                    serde_json::json!({
+                        "Image": image,
+                        "Boxes": names.len(),
+                        "Names": names,
+                        "Size": size,
+                    })
+                })
+                .collect();
+
+            let images = serde_json::to_string_pretty(&images)
+                .map_err(|e| SeaboxError::Parse(format!("Failed to serialize images: {e}")))?;
+            println!("{images}");
+            return Ok(());
+        }
+
+        if images.is_empty() {
+            eprintln!("No seabox containers found");
+            return Ok(());
+        }
+
+        let rows: Vec<[String; 4]> = images
+            .iter()
+            .map(|(image, names, size)| {
+                [
+                    image.clone(),
+                    names.len().to_string(),
+                    size.map_or("-".to_string(), format_size),
+                    names.join(", "),
+                ]
+            })
+            .collect();
+
+        let header = ["IMAGE", "BOXES", "SIZE", "NAMES"].map(String::from);
+        let widths: Vec<usize> = (0..4)
+            .map(|i| {
+                rows.iter()
+                    .chain(std::iter::once(&header))
+                    .map(|row| row[i].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for row in std::iter::once(&header).chain(rows.iter()) {
+            println!(
+                "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2]
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Stop with the container's own grace period, set by --stop-timeout when it was created
+    pub fn generate_container_stop_command(&self, name: &str) -> Vec<String> {
+        self.engine_command(&["stop", name])
+    }
+
+    /// Grace period of stopped containers. podman stop is used to stop boxes, and without a stop
+    /// signal the shell that is PID 1 would ignore SIGTERM, so by default skip the grace period
+    /// and kill immediately
+    fn stop_timeout(&self) -> u64 {
+        match (self.config.stop_timeout, &self.config.stop_signal) {
+            (Some(timeout), _) => timeout,
+            (None, Some(_)) => DEFAULT_STOP_TIMEOUT,
+            (None, None) => 0,
+        }
+    }
+
+    pub fn generate_container_delete_command(&self, name: &str, volumes: bool) -> Vec<String> {
+        let mut command: Vec<String> = self.engine_command(&["container", "rm", "--force"]);
+
+        // Only anonymous volumes are removed, never named volumes or bind mounts
+        if volumes {
+            command.push("--volumes".to_string());
+        }
+
+        command.push(name.to_string());
+        command
+    }
+
+    pub fn generate_container_start_command(&self, name: &str) -> Vec<String> {
+        self.engine_command(&["start", name])
+    }
+
+    pub fn generate_list_container_names_command(&self) -> Vec<String> {
+        let mut command = self.generate_list_containers_command();
+        command.extend(["--format".to_string(), "{{.Names}}".to_string()]);
+        command
+    }
+
+    /// Names of all containers created by seabox
+    fn list_box_names(&self) -> Result<Vec<String>> {
+        let list_names_command = self.generate_list_container_names_command();
+        let result = self.output(&list_names_command)?;
+
+        if !result.status.success() {
+            return Err(SeaboxError::CommandFailed(format!(
+                "Failed to list containers: {}",
+                String::from_utf8_lossy(&result.stderr).trim_end()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&result.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    /// Existing boxes with names close to a name that wasn't found, closest first
+    fn similar_box_names(&self, name: &str) -> Vec<String> {
+        // Only a hint, so listing failures are ignored
+        let names = self.list_box_names().unwrap_or_default();
+
+        let max_distance = (name.chars().count() / 3).max(2);
+        let mut similar: Vec<(usize, String)> = names
+            .into_iter()
+            .map(|x| (edit_distance(name, &x), x))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        similar.sort();
+
+        similar.into_iter().take(3).map(|(_, x)| x).collect()
+    }
+
+    /// The named containers, or every seabox container if `all_boxes` is set
+    fn resolve_targets(
+        &self,
+        names: &[String],
+        all_boxes: bool,
+        args: &AllCommandArgs,
+    ) -> Result<Vec<String>> {
+        if !all_boxes {
+            return Ok(names.to_vec());
+        }
+
+        if args.dry_run {
+            self.print_command("list-names", self.generate_list_container_names_command());
+        }
+
+        let names = self.list_box_names()?;
+        if names.is_empty() {
+            println!("No seabox containers found");
+        }
+
+        Ok(names)
+    }
+
+    pub fn handle_restart(&mut self, args: &RestartArgs) -> Result<()> {
+        let names = self.resolve_targets(&args.names, args.all_boxes, &args.all)?;
+        let mut failed = vec![];
+
+        for name in &names {
+            self.use_box_engine_mode(name)?;
+
+            let stop_container_command = self.generate_container_stop_command(name);
+            let start_container_command = self.generate_container_start_command(name);
+
+            if args.all.dry_run {
+                self.print_command("stop", stop_container_command);
+                self.print_command("start", start_container_command);
+                if args.wait {
+                    self.print_command("inspect", self.generate_container_inspect_command(name));
+                }
+            } else {
+                let _result = self.status_quiet(&stop_container_command)?;
+                let result = self.status_quiet(&start_container_command)?;
+
+                if result.success() && args.wait {
+                    match self.wait_until_ready(name, args.wait_timeout)? {
+                        (true, state) => println!("{name}: restarted ({state})"),
+                        (false, state) => {
+                            println!(
+                                "{name}: restarted, but not ready after {}s ({state})",
+                                args.wait_timeout
+                            );
+                            failed.push(name.clone());
+                        }
+                    }
+                } else if result.success() {
+                    println!("{name}: restarted");
+                } else {
+                    println!("{name}: failed to start ({result})");
+                    failed.push(name.clone());
+                }
+            }
+        }
+
+        summarize("restart", &names, &failed)
+    }
+
+    /// Poll the container until it is running and healthy, or the timeout passes.
+    /// Returns whether it became ready, and its last state.
+    fn wait_until_ready(&self, name: &str, timeout: u64) -> Result<(bool, String)> {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout);
+
+        loop {
+            let info = self.inspect_container(name)?;
+            let state = &info.state;
+
+            // Images without a healthcheck report an empty status, if any
+            let health = state
+                .health
+                .as_ref()
+                .map(|x| x.status.as_str())
+                .filter(|x| !x.is_empty());
+
+            let description = match health {
+                _ if !state.running => "not running".to_string(),
+                Some(status) => status.to_string(),
+                None => "running".to_string(),
+            };
+
+            // A container that stopped won't start by itself
+            if !state.running {
+                return Ok((false, description));
+            }
+
+            if health.is_none_or(|x| x == "healthy") {
+                return Ok((true, description));
+            }
+
+            if std::time::Instant::now() >= deadline {
+                return Ok((false, description));
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+    }
+
+    pub fn handle_stop(&mut self, args: &StopArgs) -> Result<()> {
+        let names = self.resolve_targets(&args.names, args.all_boxes, &args.all)?;
+        let mut failed = vec![];
+
+        for name in &names {
+            self.use_box_engine_mode(name)?;
+
+            let stop_container_command = self.generate_container_stop_command(name);
+
+            if args.all.dry_run {
+                self.print_command("stop", stop_container_command);
+            } else {
+                let result = self.status_quiet(&stop_container_command)?;
+
+                if result.success() {
+                    println!("{name}: stopped");
+                } else {
+                    println!("{name}: failed to stop ({result})");
+                    failed.push(name.clone());
+                }
+            }
+        }
+
+        summarize("stop", &names, &failed)
+    }
+
+    /// Print a command a dry run would execute, either shell-quoted or as a JSON object
+    fn print_command(&self, purpose: &str, command_args: Vec<String>) {
+        if self.dry_run_output == DryRunOutput::Json {
+            let command = serde_json::json!({ "argv": command_args, "purpose": purpose });
+            println!("{command}");
+            return;
+        }
+
+        match shlex::try_join(command_args.iter().map(|x| &**x)) {
+            Ok(command) => println!("{}", &command),
+            Err(e) => {
+                // Arguments with nul bytes can't be quoted for a shell, print them as-is instead
+                eprintln!("Warning: command cannot be shell-quoted ({e}), printing raw arguments");
+                println!("{:?}", command_args);
+            }
+        }
+    }
+
+    pub fn handle_update(&mut self, args: &UpdateArgs) -> Result<()> {
+        self.use_box_engine_mode(&args.name)?;
+
+        if args.all.dry_run {
+            self.print_command(
+                "inspect",
+                self.generate_container_inspect_command(&args.name),
+            );
+        }
+
+        let info = self.inspect_container(&args.name)?;
+        let expires = self
+            .read_box_metadata(&args.name)?
+            .and_then(|metadata| metadata.expires);
+
+        let mut creation_args = creation_args_from_inspect(&info);
+        creation_args.rootless = Some(self.config.rootless);
+        // Keep honouring --allow-any-image if the container was created with it
+        creation_args.allow_any_image = args.allow_any_image
+            || self
+                .read_box_lock(&args.name)?
+                .is_some_and(|lock| lock.args.allow_any_image);
+        // The creation args come from the container, not the command line
+        self.resolve_config_layers(&creation_args, false)?;
+
+        eprintln!(
+            "Warning: updating '{}' discards its writable layer - changes made outside of mounted directories will be lost",
+            args.name
+        );
+
+        if !creation_args.allow_any_image {
+            self.check_image_allowed(&info.image_name)?;
+        }
+
+        // Pull before removing anything, so a failed pull leaves the container intact
+        let image_pull_command = self.generate_image_pull_command(&info.image_name);
+        let stop_container_command = self.generate_container_stop_command(&args.name);
+        let delete_container_command =
+            self.generate_container_delete_command(&args.name, self.config.remove_volumes);
+
+        if args.all.dry_run {
+            self.print_command("pull", image_pull_command);
+            self.print_command("stop", stop_container_command);
+            self.print_command("remove", delete_container_command);
+        } else {
+            let pull = self.status(&image_pull_command)?;
+
+            if !pull.success() {
+                return Err(SeaboxError::ImageNotFound(info.image_name.clone()));
+            }
+
+            let _result = self.status_quiet(&stop_container_command)?;
+            let _result = self.status(&delete_container_command)?;
+        }
+
+        self.create_box(&args.name, &creation_args, &args.all, expires, true)
+    }
+
+    pub fn handle_recreate(&mut self, args: &RecreateArgs) -> Result<()> {
+        self.recreate_box(&args.name, &args.all, true)
+    }
+
+    fn recreate_box(&mut self, name: &str, all: &AllCommandArgs, enter: bool) -> Result<()> {
+        let lock = self.read_box_lock(name)?.ok_or_else(|| {
+            SeaboxError::Metadata(format!(
+                "No record of how '{name}' was created - only containers created by this version of seabox can be recreated"
+            ))
+        })?;
+
+        let expires = self
+            .read_box_metadata(name)?
+            .and_then(|metadata| metadata.expires);
+
+        // Ignore the current config file entirely, the recorded config is already merged
+        self.config = lock.config;
+
+        if all.dry_run && self.dry_run_output == DryRunOutput::Text {
+            println!("{:#?}", self.config);
+        }
+
+        let container_inspect_command = self.generate_container_inspect_command(name);
+        let stop_container_command = self.generate_container_stop_command(name);
+        let delete_container_command =
+            self.generate_container_delete_command(name, self.config.remove_volumes);
+
+        if all.dry_run {
+            self.print_command("inspect", container_inspect_command);
+            self.print_command("stop", stop_container_command);
+            self.print_command("remove", delete_container_command);
+        } else if self.status_quiet(&container_inspect_command)?.success() {
+            eprintln!(
+                "Warning: recreating '{name}' discards its writable layer - changes made outside of mounted directories will be lost"
+            );
+
+            let _result = self.status_quiet(&stop_container_command)?;
+            let result = self.status(&delete_container_command)?;
+
+            if !result.success() {
+                return Err(SeaboxError::CommandFailed(format!(
+                    "Failed to remove container '{name}'"
+                )));
+            }
+        }
+
+        self.create_box(name, &lock.args, all, expires, enter)
+    }
+
+    pub fn handle_explain_idmap(&self, args: &ExplainIdmapArgs) -> Result<()> {
+        let host_user_id = self.config.host_uid.unwrap_or_else(|| host_ids().0);
+        let host_user_gid = self.config.host_gid.unwrap_or_else(|| host_ids().1);
+
+        let option = idmap_option(
+            args.root,
+            (host_user_id, host_user_gid),
+            (
+                args.uid.unwrap_or(DEFAULT_USER_ID),
+                args.gid.unwrap_or(DEFAULT_USER_ID),
+            ),
+        );
+
+        println!("{}", option.trim_start_matches(','));
+        for line in explain_idmap_option(&option) {
+            println!("  {line}");
+        }
+        println!("  Files of other host ids show up as owned by nobody in the container");
+
+        if self.config.no_idmap {
+            eprintln!("Note: no_idmap is set, so mounts are currently not idmapped");
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_which(&mut self, args: &WhichArgs) -> Result<()> {
+        self.use_box_engine_mode(&args.name)?;
+
+        let info = &self.inspect_container(&args.name)?;
+
+        let value = match args.field {
+            WhichField::Id => info.id.clone(),
+            WhichField::Image => info.image_name.clone(),
+            WhichField::User => info.config.user.clone(),
+            WhichField::Mount => {
+                let target = mount_target(info);
+                info.mounts
+                    .iter()
+                    .find(|m| m.destination.trim_end_matches('/') == target)
+                    .map(|m| m.source.clone())
+                    .ok_or_else(|| {
+                        SeaboxError::InvalidArgument(format!(
+                            "Container '{}' has no mounted host directory",
+                            args.name
+                        ))
+                    })?
+            }
+        };
+
+        println!("{value}");
+
+        Ok(())
+    }
+
+    pub fn handle_config_show(&self, format: ConfigFormat) -> Result<()> {
+        let cfg = get_configuration_file_path();
+
+        let file_contents = fs::read_to_string(&cfg);
+        if let Ok(x) = file_contents {
+            let converted = format_configuration_file(&x, format)
+                .map_err(|e| SeaboxError::Config(format!("{e} ('{cfg}')")))?;
+
+            // Converted output is meant for other tools, so it isn't decorated
+            if let ConfigFormat::Toml = format {
+                println!("# Viewing '{}'", &cfg);
+            }
+            println!("{}", converted.trim_end());
+            Ok(())
+        } else {
+            Err(SeaboxError::Config(format!(
+                "Config file not found at {}",
+                &cfg
+            )))
+        }
+    }
+
+    pub fn handle_config_schema(&self) -> Result<()> {
+        let schema = schemars::schema_for!(ConfigFileFormat);
+
+        let schema = serde_json::to_string_pretty(&schema)
+            .map_err(|e| SeaboxError::Parse(format!("Failed to serialize schema: {e}")))?;
+        println!("{schema}");
+
+        Ok(())
+    }
+
+    pub fn handle_config_init(&self, force: bool) -> Result<()> {
+        let path = get_configuration_file_path();
+        init_configuration_file_at(std::path::Path::new(&path), force)?;
+        println!("Wrote {path}");
+
+        Ok(())
+    }
+
+    pub fn handle_config_dirs(&self, json: bool) -> Result<()> {
+        let config = get_configuration_file_path();
+        let cache = get_cache_directory_path();
+        let data = get_data_directory_path();
+
+        if json {
+            let dirs = serde_json::json!({
+                "config": config,
+                "cache": cache,
+                "data": data,
+            });
+            println!("{dirs:#}");
+        } else {
+            println!("config: {config}");
+            println!("cache: {cache}");
+            println!("data: {data}");
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_config_unset(&self, key: &str) -> Result<()> {
+        let path = get_configuration_file_path();
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+
+        let Some(contents) = unset_configuration_key(&contents, key)? else {
+            println!("'{key}' is not set in the config file");
+            return Ok(());
+        };
+
+        fs::write(&path, contents).map_err(|e| {
+            SeaboxError::Config(format!("Failed to write config file '{path}': {e}"))
+        })?;
+        println!("Removed '{key}' from {path}");
+
+        Ok(())
+    }
+
+    /// Podman with the given arguments, run through the sudo command unless rootless
+    fn engine_command(&self, args: &[&str]) -> Vec<String> {
+        let mut command = vec![];
+
+        if !self.config.rootless {
+            command.push(self.config.sudo_command.clone());
+        }
+
+        command.push("podman".to_string());
+        command.extend(args.iter().map(|x| x.to_string()));
+        command
+    }
+
+    fn box_metadata_path(&self, name: &str) -> Result<std::path::PathBuf> {
+        self.box_file_path(name, "json")
+    }
+
+    /// File of a box in the boxes directory. The name is checked so it can't point elsewhere
+    fn box_file_path(&self, name: &str, extension: &str) -> Result<std::path::PathBuf> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Invalid box name '{name}' - it can't contain path separators"
+            )));
+        }
+
+        Ok(self
+            .data_dir
+            .join("boxes")
+            .join(format!("{name}.{extension}")))
+    }
+
+    /// Metadata recorded when the box was created, if any
+    pub fn read_box_metadata(&self, name: &str) -> Result<Option<BoxInstanceFormat>> {
+        let path = self.box_metadata_path(name)?;
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(x) => x,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(SeaboxError::Metadata(format!(
+                    "Failed to read '{}': {e}",
+                    path.display()
+                )));
+            }
+        };
+
+        serde_json::from_str(&contents).map(Some).map_err(|e| {
+            SeaboxError::Metadata(format!("Failed to parse '{}': {e}", path.display()))
+        })
+    }
+
+    fn write_box_metadata(&self, metadata: &BoxInstanceFormat) -> Result<()> {
+        let path = self.box_metadata_path(&metadata.name)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                SeaboxError::Metadata(format!("Cannot create '{}': {e}", dir.display()))
+            })?;
+        }
+
+        let contents = serde_json::to_string_pretty(metadata)
+            .map_err(|e| SeaboxError::Metadata(format!("Failed to serialize metadata: {e}")))?;
+
+        fs::write(&path, contents).map_err(|e| {
+            SeaboxError::Metadata(format!("Failed to write '{}': {e}", path.display()))
+        })
+    }
+
+    fn remove_box_metadata(&self, name: &str) {
+        for path in [self.box_metadata_path(name), self.box_lock_path(name)]
+            .into_iter()
+            .flatten()
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn box_lock_path(&self, name: &str) -> Result<std::path::PathBuf> {
+        self.box_file_path(name, "lock.json")
+    }
+
+    /// Configuration and arguments recorded when the box was created, if any
+    pub fn read_box_lock(&self, name: &str) -> Result<Option<BoxLockFormat>> {
+        let path = self.box_lock_path(name)?;
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(x) => x,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(SeaboxError::Metadata(format!(
+                    "Failed to read '{}': {e}",
+                    path.display()
+                )));
+            }
+        };
+
+        serde_json::from_str(&contents).map(Some).map_err(|e| {
+            SeaboxError::Metadata(format!("Failed to parse '{}': {e}", path.display()))
+        })
+    }
+
+    fn write_box_lock(&self, name: &str, args: &CreateAndTempSharedArgs) -> Result<()> {
+        let path = self.box_lock_path(name)?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                SeaboxError::Metadata(format!("Cannot create '{}': {e}", dir.display()))
+            })?;
+        }
+
+        let lock = serde_json::json!({
+            "name": name,
+            "args": args,
+            "config": self.config,
+        });
+        let contents = serde_json::to_string_pretty(&lock)
+            .map_err(|e| SeaboxError::Metadata(format!("Failed to serialize lock: {e}")))?;
+
+        fs::write(&path, contents).map_err(|e| {
+            SeaboxError::Metadata(format!("Failed to write '{}': {e}", path.display()))
+        })
+    }
+
+    /// Target the podman storage (rootful or rootless) the box was created in
+    fn use_box_engine_mode(&mut self, name: &str) -> Result<()> {
+        if let Some(metadata) = self.read_box_metadata(name)? {
+            self.config.rootless = !metadata.rootful;
+        }
+
+        Ok(())
+    }
+
+    fn command(&self, command: &[String]) -> Command {
+        let mut process = Command::new(&command[0]);
+        process.args(&command[1..]);
+        process.envs(self.engine_env.iter().map(|(k, v)| (k, v)));
+        process
+    }
+
+    /// With --trace, print a command about to run
+    fn trace_command(&self, command: &[String]) {
+        if self.trace {
+            let command = shlex::try_join(command.iter().map(|x| &**x))
+                .unwrap_or_else(|_| format!("{command:?}"));
+            eprintln!("[trace] {command}");
+        }
+    }
+
+    /// With --trace, print how a command exited
+    fn trace_status(&self, status: &ExitStatus) {
+        if self.trace {
+            eprintln!("[trace] {status}");
+        }
+    }
+
+    /// Run a command, inheriting stdin/stdout/stderr
+    fn status(&self, command: &[String]) -> Result<ExitStatus> {
+        self.trace_command(command);
+
+        let status = self
+            .runner
+            .status(&mut self.command(command))
+            .map_err(|e| SeaboxError::spawn(&command[0], e))?;
+
+        self.trace_status(&status);
+        Ok(status)
+    }
+
+    /// Run a command, discarding its output
+    fn status_quiet(&self, command: &[String]) -> Result<ExitStatus> {
+        let mut process = self.command(command);
+        process.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        self.trace_command(command);
+
+        let status = self
+            .runner
+            .status(&mut process)
+            .map_err(|e| SeaboxError::spawn(&command[0], e))?;
+
+        self.trace_status(&status);
+        Ok(status)
+    }
+
+    /// Run a command, capturing its stderr and echoing it once the command exits
+    fn status_capture_stderr(&self, command: &[String]) -> Result<(ExitStatus, String)> {
+        let mut process = self.command(command);
+        process.stdin(Stdio::inherit()).stdout(Stdio::inherit());
+
+        self.trace_command(command);
+
+        let result = self
+            .runner
+            .output(&mut process)
+            .map_err(|e| SeaboxError::spawn(&command[0], e))?;
+
+        let stderr = String::from_utf8_lossy(&result.stderr).to_string();
+        eprint!("{stderr}");
+        self.trace_status(&result.status);
+
+        Ok((result.status, stderr))
+    }
+
+    /// Run a command, capturing its output
+    fn output(&self, command: &[String]) -> Result<Output> {
+        self.trace_command(command);
+
+        let output = self
+            .runner
+            .output(&mut self.command(command))
+            .map_err(|e| SeaboxError::spawn(&command[0], e))?;
+
+        self.trace_status(&output.status);
+        Ok(output)
+    }
+
+    /// Replace the current process with the command, or run it to completion and propagate its
+    /// exit code where the process can't be replaced
+    fn exec(&self, command: &[String]) -> Result<()> {
+        self.trace_command(command);
+
+        let status = self
+            .runner
+            .exec(&mut self.command(command))
+            .map_err(|e| SeaboxError::spawn(&command[0], e))?;
+
+        self.trace_status(&status);
+
+        match status.code() {
+            Some(0) => Ok(()),
+            Some(code) => Err(SeaboxError::SessionFailed(code)),
+            None => Err(SeaboxError::CommandFailed(format!(
+                "'{}' exited with {status}",
+                command[0]
+            ))),
+        }
+    }
+}
+
+/// Check a hosts entry has the form name:ip, where ip may also be podman's host-gateway
+fn is_valid_host_entry(host: &str) -> bool {
+    let Some((name, ip)) = host.split_once(':') else {
+        return false;
+    };
+
+    !name.is_empty()
+        && !name.contains(char::is_whitespace)
+        && (ip == "host-gateway" || ip.parse::<std::net::IpAddr>().is_ok())
+}
+
+/// Refuse to run inside a seabox container, where the host network and mounts make
+/// nested containers confusing, unless forced
+fn check_nesting(force: bool) -> Result<()> {
+    if std::env::var_os(IN_BOX_ENV).is_none() {
+        return Ok(());
+    }
+
+    if force {
+        eprintln!("Warning: running inside a seabox container");
+        return Ok(());
+    }
+
+    Err(SeaboxError::NestedBox)
+}
+
+/// Podman arguments for a --tz value, mounting the host's /etc/localtime for 'host'
+fn timezone_arguments(tz: &str) -> Result<Vec<String>> {
+    if tz != "host" {
+        if tz.is_empty() || tz.contains(char::is_whitespace) {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "Invalid timezone '{tz}' - expected a name like Europe/Berlin or 'host'"
+            )));
+        }
+        return Ok(vec!["--env".to_string(), format!("TZ={tz}")]);
+    }
+
+    let Ok(localtime) = fs::canonicalize("/etc/localtime") else {
+        return Err(SeaboxError::InvalidArgument(
+            "--tz host requires /etc/localtime on the host".to_string(),
+        ));
+    };
+
+    let mut arguments = vec![
+        "--mount".to_string(),
+        format!(
+            "type=bind,source={},destination=/etc/localtime,ro=true",
+            localtime.to_str().unwrap()
+        ),
+    ];
+
+    // The name of the zone, from TZ or the zoneinfo path /etc/localtime links to
+    let name = std::env::var("TZ")
+        .ok()
+        .filter(|x| !x.is_empty())
+        .or_else(|| {
+            localtime
+                .to_str()
+                .and_then(|x| x.split_once("/zoneinfo/"))
+                .map(|(_, name)| name.to_string())
+        });
+
+    if let Some(name) = name {
+        arguments.extend(["--env".to_string(), format!("TZ={name}")]);
+    }
+
+    Ok(arguments)
+}
+
+/// Check an enter --user value has the form name, uid, uid:gid or name:group. An empty group,
+/// as in uid:, is the user's primary group, which is how seabox itself enters boxes
+fn is_valid_user(user: &str) -> bool {
+    let valid = |x: &str| !x.is_empty() && !x.contains(char::is_whitespace);
+
+    match user.split_once(':') {
+        Some((user, "")) => valid(user),
+        Some((user, group)) => valid(user) && valid(group) && !group.contains(':'),
+        None => valid(user),
+    }
+}
+
+/// Whether an enter --user value names a user other than root, rather than giving a uid
+fn is_user_name(user: &str) -> bool {
+    let username = user.split(':').next().unwrap_or(user);
+    username != "root" && username.parse::<u32>().is_err()
+}
+
+/// Check a --group-add value is a numeric gid or a plausible group name
+fn is_valid_group(group: &str) -> bool {
+    if group.parse::<u32>().is_ok() {
+        return true;
+    }
+
+    group
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && group
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Check a label has the form key=value, with a non-empty key
+fn is_valid_label(label: &str) -> bool {
+    label
+        .split_once('=')
+        .is_some_and(|(key, _)| !key.trim().is_empty() && !key.contains(char::is_whitespace))
+}
+
+/// Read the key=value labels of a --label-file, skipping blank lines and comments
+fn read_label_file(path: &str) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        SeaboxError::InvalidArgument(format!("Failed to read label file '{path}': {e}"))
+    })?;
+
+    let mut labels = vec![];
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !is_valid_label(line) {
+            return Err(SeaboxError::InvalidArgument(format!(
+                "{path}:{}: invalid label '{line}' - expected 'key=value'",
+                index + 1
+            )));
+        }
+        labels.push(line.to_string());
+    }
+
+    Ok(labels)
+}
+
+/// Check a --cpuset-cpus value is a comma separated list of CPU indices and ascending ranges
+fn is_valid_cpuset(cpuset: &str) -> bool {
+    cpuset.split(',').all(|part| match part.split_once('-') {
+        Some((first, last)) => match (first.parse::<u32>(), last.parse::<u32>()) {
+            (Ok(first), Ok(last)) => first <= last,
+            _ => false,
+        },
+        None => part.parse::<u32>().is_ok(),
+    })
+}
+
+/// Bytes of a size like 512m or 4g, with an optional b, k, m or g unit
+fn parse_memory_size(size: &str) -> Option<u64> {
+    let lower = size.to_ascii_lowercase();
+    let digits = lower.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+
+    let multiplier: u64 = match &lower[digits.len()..] {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        _ => return None,
+    };
+
+    digits
+        .parse::<u64>()
+        .ok()
+        .filter(|x| *x > 0)
+        .and_then(|x| x.checked_mul(multiplier))
+}
+
+/// Seconds of a duration like 90s, 30m, 2h, 7d or 1w
+fn parse_duration(duration: &str) -> Option<u64> {
+    let digits = duration.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+
+    let multiplier: u64 = match &duration[digits.len()..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    digits
+        .parse::<u64>()
+        .ok()
+        .filter(|x| *x > 0)
+        .and_then(|x| x.checked_mul(multiplier))
+}
+
+/// Effective uid and gid of seabox, which idmapped mounts map the container user to
+#[cfg(unix)]
+fn host_ids() -> (u32, u32) {
+    (
+        nix::unistd::geteuid().as_raw(),
+        nix::unistd::getegid().as_raw(),
+    )
+}
+
+/// Hosts without unix ids talk to a remote podman machine, whose default user has uid 1000
+#[cfg(not(unix))]
+fn host_ids() -> (u32, u32) {
+    (1000, 1000)
+}
+
+/// Seconds since the Unix epoch
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs())
+}
+
+/// A size in bytes in the largest unit it has at least one of, like podman prints image sizes
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "kB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < units.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", units[unit])
+    }
+}
+
+/// Mount option for the idmap of a single --volume: "none" to not idmap it, HOST-CONTAINER-COUNT
+/// ranges joined by '#' for both uids and gids, or podman's own "uids=...;gids=..." form
+fn volume_idmap_option(spec: &str) -> Result<String> {
+    if spec == "none" {
+        return Ok(String::new());
+    }
+
+    if spec.starts_with("uids=") || spec.starts_with("gids=") {
+        return Ok(format!(",idmap={spec}"));
+    }
+
+    let valid = spec.split('#').all(|range| {
+        let parts: Vec<&str> = range.split('-').collect();
+        parts.len() == 3 && parts.iter().all(|x| x.parse::<u32>().is_ok())
+    });
+
+    if !valid {
+        return Err(SeaboxError::InvalidArgument(format!(
+            "Invalid volume idmap '{spec}' - expected 'none', 'HOST-CONTAINER-COUNT' ranges joined by '#', or 'uids=...;gids=...'"
+        )));
+    }
+
+    Ok(format!(",idmap=uids={spec};gids={spec}"))
+}
+
+/// Check a --log-opt value has the form key=value
+fn is_valid_log_opt(log_opt: &str) -> bool {
+    log_opt.split_once('=').is_some_and(|(key, value)| {
+        !key.is_empty() && !key.contains(char::is_whitespace) && !value.is_empty()
+    })
+}
+
+/// Check a --ulimit value has the form name=soft:hard or name=value, or is host to copy the
+/// limits of the host
+fn is_valid_ulimit(ulimit: &str) -> bool {
+    if ulimit == "host" {
+        return true;
+    }
+
+    let Some((name, limits)) = ulimit.split_once('=') else {
+        return false;
+    };
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+
+    let valid_limit = |x: &str| x.parse::<i64>().is_ok_and(|x| x >= -1);
+
+    match limits.split_once(':') {
+        Some((soft, hard)) => valid_limit(soft) && valid_limit(hard),
+        None => valid_limit(limits),
+    }
+}
+
+/// Add the idmap option to a --mount spec of a bind mount that doesn't set one itself
+fn mount_with_idmap(spec: &str, idmap_option: &str) -> String {
+    let options: Vec<&str> = spec.split(',').map(str::trim).collect();
+
+    let is_bind = options.contains(&"type=bind");
+    let has_idmap = options.iter().any(|x| x.split('=').next() == Some("idmap"));
+
+    if is_bind && !has_idmap {
+        format!("{spec}{idmap_option}")
+    } else {
+        spec.to_string()
+    }
+}
+
+fn is_selinux_enforcing() -> bool {
+    fs::read_to_string("/sys/fs/selinux/enforce").is_ok_and(|x| x.trim() == "1")
+}
+
+/// Whether the engine failed because the configured OCI runtime couldn't be found
+fn is_runtime_error(stderr: &str, runtime: &str) -> bool {
+    stderr.lines().any(|line| {
+        line.contains("runtime") && line.contains(runtime) && line.contains("not found")
+    })
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Mount option idmapping the host user to the container user, or the first ids to themselves for
+/// root containers. Root is always mapped to root
+fn idmap_option(root: bool, host_ids: (u32, u32), container_ids: (i64, i64)) -> String {
+    if root {
+        return ",idmap=uids=0-0-2000;gids=0-0-2000".to_string();
+    }
+
+    let (host_user_id, host_user_gid) = host_ids;
+    let (container_user_id, container_user_gid) = container_ids;
+    format!(
+        ",idmap=uids={host_user_id}-{container_user_id}-1#0-0-1;gids={host_user_gid}-{container_user_gid}-1#0-0-1",
+    )
+}
+
+/// Describe each HOST-CONTAINER-COUNT range of an idmap mount option
+fn explain_idmap_option(option: &str) -> Vec<String> {
+    let spec = option.trim_start_matches(',').trim_start_matches("idmap=");
+
+    let mut lines = vec![];
+    for part in spec.split(';') {
+        let Some((kind, ranges)) = part.split_once('=') else {
+            continue;
+        };
+        let kind = kind.trim_end_matches('s');
+
+        for range in ranges.split('#') {
+            let numbers: Vec<u64> = range.split('-').filter_map(|x| x.parse().ok()).collect();
+            let [host, container, count] = numbers[..] else {
+                continue;
+            };
+
+            lines.push(if count == 1 {
+                format!("{range}: host {kind} {host} is container {kind} {container}")
+            } else {
+                format!(
+                    "{range}: host {kind}s {host}-{} are container {kind}s {container}-{}",
+                    host + count - 1,
+                    container + count - 1
+                )
+            });
+        }
+    }
+
+    lines
+}
+
+/// Check a --stop-signal value is a known signal name, with or without SIG, or a signal number
+fn is_valid_signal(signal: &str) -> bool {
+    const SIGNALS: &[&str] = &[
+        "HUP", "INT", "QUIT", "ILL", "TRAP", "ABRT", "IOT", "BUS", "FPE", "KILL", "USR1", "SEGV",
+        "USR2", "PIPE", "ALRM", "TERM", "STKFLT", "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU",
+        "URG", "XCPU", "XFSZ", "VTALRM", "PROF", "WINCH", "IO", "POLL", "PWR", "SYS",
+    ];
+
+    if let Ok(number) = signal.parse::<u8>() {
+        return (1..=64).contains(&number);
+    }
+
+    let name = signal.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    SIGNALS.contains(&name)
+}
+
+/// Name of a --secret value, before its options
+fn secret_name(secret: &str) -> &str {
+    secret.split(',').next().unwrap_or(secret)
+}
+
+/// Check a --secret value is a name, optionally followed by comma separated key=value options
+fn is_valid_secret(secret: &str) -> bool {
+    let mut parts = secret.split(',');
+
+    let name_valid = parts.next().is_some_and(|name| {
+        !name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c == '=')
+    });
+
+    name_valid
+        && parts.all(|option| {
+            option
+                .split_once('=')
+                .is_some_and(|(key, value)| !key.is_empty() && !value.is_empty())
+        })
+}
+
+/// Loose check of the shape of a --security-opt value, the engine validates the details
+fn is_valid_security_opt(opt: &str) -> bool {
+    if opt.is_empty() || opt.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    if opt == "no-new-privileges" {
+        return true;
+    }
+
+    match opt.split_once(['=', ':']) {
+        Some((key, _)) => !key.is_empty(),
+        None => false,
+    }
+}
+
+/// User to enter a container as when none is given. Prefers the uid resolved on creation,
+/// falling back to the user the container runs as for containers without it. The GID is left
+/// blank so the user keeps its supplementary groups.
+fn default_enter_user(info: &PodmanContainerInspectFormat) -> String {
+    let labels = info.config.labels.as_ref();
+    let label = |key: &str| labels.and_then(|labels| labels.get(key));
+
+    if let Some(uid) = label(RESOLVED_UID_LABEL) {
+        return format!("{uid}:");
+    }
+
+    let user = info.config.user.trim();
+    if user.split(':').next().is_some_and(|uid| !uid.is_empty()) {
+        return user.to_string();
+    }
+
+    // Name of the user created by the init script
+    NEW_USER_USERNAME.to_string()
+}
+
+/// Container path the primary host directory of a container is mounted to
+fn mount_target(info: &PodmanContainerInspectFormat) -> &str {
+    info.config
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get(MOUNT_TARGET_LABEL))
+        .map(|x| x.trim_end_matches('/'))
+        .unwrap_or(DEFAULT_MOUNT_TARGET)
+}
+
+/// Path in the container corresponding to the current directory, given the host directory mounted at `target`.
+/// Falls back to `target` when the current directory is outside of the host directory.
+fn mount_relative_workdir(host_dir: &str, target: &str) -> String {
+    // Resolve symlinks on both sides, or a symlink in either path makes them look unrelated
+    let resolve = |path: &std::path::Path| {
+        fs::canonicalize(path)
+            .or_else(|_| std::path::absolute(path))
+            .expect("Couldn't make path absolute")
+    };
+
+    let absolute_path = resolve(std::path::Path::new(host_dir));
+
+    let current_dir = std::env::current_dir().expect("Current working directory not found");
+    let cwd_path = resolve(&current_dir);
+    let relative_path = cwd_path
+        .strip_prefix(absolute_path)
+        .ok()
+        .and_then(|x| x.to_str())
+        .unwrap_or("")
+        .to_string();
+
+    format!("{target}/{relative_path}")
+}
+
+/// Recover the arguments a container was created with from its inspect output
+fn creation_args_from_inspect(info: &PodmanContainerInspectFormat) -> CreateAndTempSharedArgs {
+    let uid = info
+        .config
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get(RESOLVED_UID_LABEL))
+        .map(|x| x.as_str())
+        .or(info.config.user.split(':').next());
+
+    let mut args = CreateAndTempSharedArgs {
+        image: Some(info.image_name.clone()),
+        root: Some(uid == Some("0")),
+        ..Default::default()
+    };
+
+    let target = mount_target(info);
+    if info
+        .mounts
+        .iter()
+        .any(|mount| mount.source == target && mount.destination.trim_end_matches('/') == target)
+    {
+        args.mount_at_host_path = Some(true);
+    } else if target != DEFAULT_MOUNT_TARGET {
+        args.mount_into_home = Some(true);
+    }
+
+    let mut volumes = vec![];
+    let mut caches = vec![];
+    for mount in &info.mounts {
+        if mount.kind == "volume" {
+            if let Some(cache) = mount.name.strip_prefix(&format!("{SEABOX_NAME}-cache-")) {
+                caches.push(format!("{cache}:{}", mount.destination));
+            }
+            continue;
+        }
+
+        match mount.destination.trim_end_matches('/') {
+            destination if destination == target => args.directory = Some(mount.source.clone()),
+            "/etc/hosts" => args.hosts_file = Some(mount.source.clone()),
+            SHELL_RCFILE_PATH => args.shell_rcfile = Some(mount.source.clone()),
+            "/etc/localtime" => args.tz = Some("host".to_string()),
+            "/etc/resolv.conf" => args.resolv_conf = Some(mount.source.clone()),
+            destination => volumes.push(format!("{}:{}", mount.source, destination)),
+        }
+    }
+
+    if !volumes.is_empty() {
+        args.volume = Some(volumes);
+    }
+
+    if !caches.is_empty() {
+        args.mount_cache = Some(caches);
+    }
+
+    args
+}
+
+/// Split a --mount-cache NAME:CONTAINER_PATH value into the volume name and container path
+fn parse_cache_spec(spec: &str) -> Result<(String, String)> {
+    let invalid = || {
+        SeaboxError::InvalidArgument(format!(
+            "Invalid cache mount '{spec}' - expected 'NAME:CONTAINER_PATH'"
+        ))
+    };
+
+    let (name, container_dir) = spec.split_once(':').ok_or_else(invalid)?;
+
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+
+    if !valid_name || !container_dir.starts_with('/') {
+        return Err(invalid());
+    }
+
+    Ok((
+        format!("{SEABOX_NAME}-cache-{name}"),
+        container_dir.to_string(),
+    ))
+}
+
+/// Split a list of volume specs separated by ';', skipping empty entries
+fn parse_volume_list(volumes: &str) -> Vec<String> {
+    volumes
+        .split(';')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Validate a KEY=VALUE session variable, resolving a bare KEY from the host environment.
+/// Forwarded explicitly since the sudo command typically resets the environment.
+fn resolve_session_env(var: &str) -> Result<String> {
+    let (key, value) = match var.split_once('=') {
+        Some((key, value)) => (key, Some(value)),
+        None => (var, None),
+    };
+
+    let valid_key = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !valid_key {
+        return Err(SeaboxError::InvalidArgument(format!(
+            "Invalid environment variable '{var}' - expected 'KEY=VALUE' or 'KEY'"
+        )));
+    }
+
+    match value {
+        Some(value) => Ok(format!("{key}={value}")),
+        None => match std::env::var(key) {
+            Ok(value) => Ok(format!("{key}={value}")),
+            Err(_) => Err(SeaboxError::InvalidArgument(format!(
+                "Environment variable '{key}' is not set on the host"
+            ))),
+        },
+    }
+}
+
+/// Split a KEY=VALUE engine environment variable
+fn parse_engine_env(var: &str) -> Result<(String, String)> {
+    match var.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(SeaboxError::InvalidArgument(format!(
+            "Invalid engine environment variable '{var}' - expected 'KEY=VALUE'"
+        ))),
+    }
+}
+
+/// Whether the engine failed because idmapped mounts are not supported
+///
+/// Only the runtime's messages count, a malformed `idmap=` option also mentions idmap
+fn is_idmap_error(stderr: &str) -> bool {
+    stderr.lines().any(|line| {
+        let line = line.to_lowercase();
+        line.contains("mount_setattr")
+            || (line.contains("idmap")
+                && ["not supported", "unsupported", "does not support"]
+                    .iter()
+                    .any(|x| line.contains(x)))
+    })
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no when there is nobody to answer
+fn confirm(question: &str) -> bool {
+    if !std::io::stdin().is_terminal() {
+        eprintln!("{question}no, stdin is not a terminal");
+        return false;
+    }
+
+    eprint!("{question}");
+
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+/// Fail if any of the containers in a bulk operation failed
+fn summarize(operation: &str, names: &[String], failed: &[String]) -> Result<()> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    Err(SeaboxError::CommandFailed(format!(
+        "Failed to {operation} {} of {} containers: {}",
+        failed.len(),
+        names.len(),
+        failed.join(", ")
+    )))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_initial_enter_script(
+    template: &str,
+    create_user: bool,
+    username: &str,
+    container_user_id: i64,
+    passwordless_sudo: bool,
+    no_password: bool,
+    install_sudo: Option<bool>,
+    shell: Option<String>,
+    verbose: bool,
+    setup_only: bool,
+) -> String {
+    let param_sudo_install_prompt = {
+        match install_sudo {
+            Some(true) => "install",
+            Some(false) => "no_install",
+            None => "prompt",
+        }
+    };
+
+    let shell = shell.unwrap_or("".to_string());
+
+    template
+        .replace("INSERT_CREATE_USER", if create_user { "1" } else { "" })
+        .replace("INSERT_NEW_USERNAME", username)
+        .replace("INSERT_CONTAINER_ID", &container_user_id.to_string())
+        .replace("INSERT_SUDO_INSTALL", param_sudo_install_prompt)
+        .replace(
+            "INSERT_PASSWORDLESS_SUDO",
+            if passwordless_sudo { "1" } else { "" },
+        )
+        .replace("INSERT_CREATE_PASSWORD", if no_password { "1" } else { "" })
+        .replace("INSERT_VERBOSE", if verbose { "1" } else { "" })
+        .replace("INSERT_SHELL", &shell)
+        .replace("INSERT_SETUP_ONLY", if setup_only { "1" } else { "" })
+}
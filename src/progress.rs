@@ -0,0 +1,36 @@
+use std::io::{IsTerminal, Write};
+
+/// A status line on stderr for operations that take a while without output of their own.
+/// Only shown on a terminal, and cleared again when dropped.
+pub(crate) struct Progress {
+    shown: bool,
+}
+
+impl Progress {
+    pub(crate) fn show(message: &str, enabled: bool) -> Progress {
+        let mut stderr = std::io::stderr();
+        let shown = enabled && stderr.is_terminal();
+
+        if shown {
+            let _ = write!(stderr, "{message}");
+            let _ = stderr.flush();
+        }
+
+        Progress { shown }
+    }
+
+    /// Clear the status line
+    pub(crate) fn finish(&mut self) {
+        if self.shown {
+            // Return to the start of the line and clear it
+            eprint!("\r\x1b[2K");
+            self.shown = false;
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
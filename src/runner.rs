@@ -0,0 +1,95 @@
+use std::io;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::process::{Command, ExitStatus, Output};
+
+/// Spawns the engine commands built by a [`crate::Context`].
+///
+/// The default [`ProcessRunner`] runs them directly. Other implementations can redirect
+/// or record the commands, for example to drive seabox against a fake engine in tests.
+pub trait Runner {
+    /// Run the command to completion
+    fn status(&self, command: &mut Command) -> io::Result<ExitStatus>;
+
+    /// Run the command to completion, capturing stdout and stderr
+    fn output(&self, command: &mut Command) -> io::Result<Output>;
+
+    /// Replace the current process with the command. Implementations that can't
+    /// replace the process run the command to completion and return its status instead.
+    fn exec(&self, command: &mut Command) -> io::Result<ExitStatus>;
+}
+
+pub struct ProcessRunner;
+
+impl Runner for ProcessRunner {
+    fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        command.status()
+    }
+
+    fn output(&self, command: &mut Command) -> io::Result<Output> {
+        command.output()
+    }
+
+    #[cfg(unix)]
+    fn exec(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        Err(command.exec())
+    }
+
+    /// Platforms without exec, like Windows with a remote podman machine, wait for the command
+    #[cfg(not(unix))]
+    fn exec(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        command.status()
+    }
+}
+
+/// Keeps seabox running when it is interrupted, while installed.
+///
+/// A handler that does nothing is installed rather than ignoring the signals, as ignored
+/// signals would stay ignored in the engine process it spawns.
+pub struct InterruptGuard {
+    #[cfg(unix)]
+    previous: Vec<(nix::sys::signal::Signal, nix::sys::signal::SigAction)>,
+}
+
+#[cfg(unix)]
+extern "C" fn ignore_signal(_: nix::libc::c_int) {}
+
+impl InterruptGuard {
+    #[cfg(unix)]
+    pub fn install() -> Self {
+        use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction};
+
+        let action = SigAction::new(
+            SigHandler::Handler(ignore_signal),
+            SaFlags::SA_RESTART,
+            SigSet::empty(),
+        );
+
+        let previous = [Signal::SIGINT, Signal::SIGTERM, Signal::SIGHUP]
+            .into_iter()
+            // SAFETY: the handler does nothing, so it is async-signal-safe
+            .filter_map(|signal| {
+                unsafe { sigaction(signal, &action) }
+                    .ok()
+                    .map(|x| (signal, x))
+            })
+            .collect();
+
+        InterruptGuard { previous }
+    }
+
+    #[cfg(not(unix))]
+    pub fn install() -> Self {
+        InterruptGuard {}
+    }
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        for (signal, action) in &self.previous {
+            // SAFETY: restores the action that was installed before
+            let _ = unsafe { nix::sys::signal::sigaction(*signal, action) };
+        }
+    }
+}
@@ -0,0 +1,277 @@
+//! Reading and writing the config file.
+
+use seabox::{
+    BaseConfig, ConfigFileFormat, ConfigFormat, SeaboxError, UnsetVariables,
+    format_configuration_file, init_configuration_file_at, interpolate, resolve_image_profile,
+    unset_configuration_key, write_configuration_file_to,
+};
+use std::path::PathBuf;
+
+fn temp_dir(test_name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "seabox-config-test-{}-{}",
+        std::process::id(),
+        test_name
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn config_file() -> ConfigFileFormat {
+    ConfigFileFormat {
+        base: BaseConfig {
+            image: Some("docker.io/library/alpine:latest".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn write_creates_missing_config_directory() {
+    let dir = temp_dir("create_dir");
+    let path = dir.join("nested/seabox/seabox.toml");
+
+    write_configuration_file_to(&path, &config_file()).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents, "image = \"docker.io/library/alpine:latest\"\n");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn init_writes_parseable_starter_config_once() {
+    let dir = temp_dir("init");
+    let path = dir.join("seabox/seabox.toml");
+
+    init_configuration_file_at(&path, false).unwrap();
+
+    let parsed: ConfigFileFormat =
+        toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(parsed.base.image.is_some());
+    assert_eq!(parsed.base.sudo_command.as_deref(), Some("sudo"));
+    assert!(
+        parsed
+            .image_specific
+            .contains_key("docker.io/library/alpine:latest")
+    );
+
+    std::fs::write(&path, "image = \"mine\"\n").unwrap();
+    let result = init_configuration_file_at(&path, false);
+    assert!(matches!(result, Err(SeaboxError::Config(_))));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "image = \"mine\"\n"
+    );
+
+    init_configuration_file_at(&path, true).unwrap();
+    assert_ne!(
+        std::fs::read_to_string(&path).unwrap(),
+        "image = \"mine\"\n"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn image_profiles_inherit_from_their_base() {
+    let file: ConfigFileFormat = toml::from_str(
+        r#"
+image = "top"
+
+["common"]
+install_sudo = true
+hostname = "common"
+ulimit = ["nofile=1024"]
+
+["ubuntu"]
+base = "common"
+hostname = "ubuntu"
+
+["ubuntu-dev"]
+base = "ubuntu"
+ulimit = ["nofile=65536"]
+
+["loop-a"]
+base = "loop-b"
+
+["loop-b"]
+base = "loop-a"
+
+["orphan"]
+base = "missing"
+"#,
+    )
+    .unwrap();
+
+    let profile = resolve_image_profile(&file, "ubuntu-dev").unwrap().unwrap();
+    assert_eq!(profile.install_sudo, Some(true));
+    assert_eq!(profile.hostname.as_deref(), Some("ubuntu"));
+    assert_eq!(profile.ulimit, Some(vec!["nofile=65536".to_string()]));
+    assert_eq!(profile.base, None);
+
+    assert!(resolve_image_profile(&file, "unknown").unwrap().is_none());
+    assert!(matches!(
+        resolve_image_profile(&file, "loop-a"),
+        Err(SeaboxError::Config(_))
+    ));
+    assert!(matches!(
+        resolve_image_profile(&file, "orphan"),
+        Err(SeaboxError::Config(_))
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn write_reports_uncreatable_config_directory() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Permissions don't restrict root
+    if nix::unistd::geteuid().is_root() {
+        return;
+    }
+
+    let dir = temp_dir("read_only");
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o500)).unwrap();
+    let config_dir = dir.join("seabox");
+
+    let result = write_configuration_file_to(&config_dir.join("seabox.toml"), &config_file());
+
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    match result {
+        Err(SeaboxError::Config(msg)) => {
+            assert!(msg.contains(&format!("'{}'", config_dir.display())));
+            assert!(msg.contains("Permission denied"));
+        }
+        _ => panic!("expected a config error"),
+    }
+}
+
+#[test]
+fn schema_documents_every_config_key() {
+    let schema = serde_json::to_value(schemars::schema_for!(ConfigFileFormat)).unwrap();
+
+    let properties = schema["$defs"]["BaseConfig"]["properties"]
+        .as_object()
+        .unwrap();
+    assert!(properties.contains_key("image"));
+
+    for (key, property) in properties {
+        assert!(
+            property["description"].is_string(),
+            "config key '{key}' has no doc comment"
+        );
+    }
+
+    // Tables named after images are profiles
+    assert_eq!(schema["additionalProperties"]["$ref"], "#/$defs/BaseConfig");
+}
+
+#[test]
+fn show_converts_profiles_and_presets_across_formats() {
+    let contents = r#"
+image = "docker.io/library/alpine:latest"
+
+[presets.net]
+network = "bridge"
+
+["docker.io/library/ubuntu:latest"]
+install_sudo = true
+"#;
+
+    let json = format_configuration_file(contents, ConfigFormat::Json).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json["image"], "docker.io/library/alpine:latest");
+    assert_eq!(json["presets"]["net"]["network"], "bridge");
+    assert_eq!(
+        json["docker.io/library/ubuntu:latest"]["install_sudo"],
+        true
+    );
+
+    // Profiles stay top-level tables, so the YAML parses back into the same config
+    let yaml = format_configuration_file(contents, ConfigFormat::Yaml).unwrap();
+    let reparsed: ConfigFileFormat = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(
+        reparsed.image_specific["docker.io/library/ubuntu:latest"].install_sudo,
+        Some(true)
+    );
+
+    let result = format_configuration_file("image = ", ConfigFormat::Json);
+    assert!(matches!(result, Err(SeaboxError::Config(_))));
+}
+
+#[test]
+fn unset_keeps_comments_and_key_order() {
+    let contents = r#"# My seabox setup
+image = "docker.io/library/alpine:latest"
+# Needed for the VPN
+network = "bridge"
+root = false
+
+[presets.net]
+# Only for this preset
+network = "host"
+
+["docker.io/library/ubuntu:latest"]
+install_sudo = true
+"#;
+
+    let unset = unset_configuration_key(contents, "network")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        unset,
+        r#"# My seabox setup
+image = "docker.io/library/alpine:latest"
+root = false
+
+[presets.net]
+# Only for this preset
+network = "host"
+
+["docker.io/library/ubuntu:latest"]
+install_sudo = true
+"#
+    );
+
+    // Tables left empty are dropped
+    let unset = unset_configuration_key(&unset, "presets.net.network")
+        .unwrap()
+        .unwrap();
+    let unset = unset_configuration_key(&unset, "docker.io/library/ubuntu:latest.install_sudo")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        unset,
+        "# My seabox setup\nimage = \"docker.io/library/alpine:latest\"\nroot = false\n"
+    );
+
+    assert_eq!(unset_configuration_key(&unset, "network").unwrap(), None);
+    assert_eq!(unset_configuration_key(&unset, "presets").unwrap(), None);
+    assert_eq!(unset_configuration_key(&unset, "dev.root").unwrap(), None);
+}
+
+#[test]
+fn interpolate_expands_environment_variables() {
+    let home = std::env::var("HOME").unwrap();
+    let unset = "${SEABOX_TEST_UNSET_VARIABLE}/x";
+
+    assert_eq!(
+        interpolate("${HOME}/projects:$HOME/b", UnsetVariables::Error).unwrap(),
+        format!("{home}/projects:{home}/b")
+    );
+    assert_eq!(
+        interpolate("$$HOME costs $5$", UnsetVariables::Error).unwrap(),
+        "$HOME costs $5$"
+    );
+    assert_eq!(interpolate(unset, UnsetVariables::Empty).unwrap(), "/x");
+    assert!(matches!(
+        interpolate(unset, UnsetVariables::Error),
+        Err(SeaboxError::Config(msg)) if msg.contains("SEABOX_TEST_UNSET_VARIABLE")
+    ));
+    assert!(interpolate("${HOME", UnsetVariables::Error).is_err());
+}
@@ -0,0 +1,3176 @@
+//! Drives seabox against `tests/fixtures/fake-engine.sh`, which stands in for `sudo podman`,
+//! and checks the engine commands that were run.
+
+use clap::Parser;
+use seabox::{BaseConfig, Cli, ConfigFileFormat, Context, Runner, SeaboxError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+
+/// Points the fake engine at the log file of a test
+struct FakeEngine {
+    log: PathBuf,
+}
+
+impl Runner for FakeEngine {
+    fn status(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        command
+            .env("SEABOX_FAKE_LOG", &self.log)
+            .stdout(Stdio::null())
+            .status()
+    }
+
+    fn output(&self, command: &mut Command) -> io::Result<Output> {
+        command.env("SEABOX_FAKE_LOG", &self.log).output()
+    }
+
+    fn exec(&self, command: &mut Command) -> io::Result<ExitStatus> {
+        self.status(command)
+    }
+}
+
+struct Harness {
+    dir: PathBuf,
+}
+
+impl Harness {
+    fn new(test_name: &str) -> Harness {
+        let dir =
+            std::env::temp_dir().join(format!("seabox-test-{}-{}", std::process::id(), test_name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Harness { dir }
+    }
+
+    fn log(&self) -> PathBuf {
+        self.dir.join("engine.log")
+    }
+
+    fn data_dir(&self) -> PathBuf {
+        self.dir.join("data")
+    }
+
+    fn run(&self, args: &[&str]) -> seabox::Result<()> {
+        let engine = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fake-engine.sh");
+
+        let config_file = ConfigFileFormat {
+            base: BaseConfig {
+                sudo_command: Some(engine.to_str().unwrap().to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let cli =
+            Cli::try_parse_from(std::iter::once("seabox").chain(args.iter().copied())).unwrap();
+
+        Context::from_config_file(config_file)?
+            .with_runner(FakeEngine { log: self.log() })
+            .with_data_dir(self.data_dir())
+            .run(cli)
+    }
+
+    /// The seabox binary, for tests that set its environment or working directory
+    fn binary(&self, args: &[&str]) -> Command {
+        let engine = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fake-engine.sh");
+
+        let mut command = Command::new(env!("CARGO_BIN_EXE_seabox"));
+        command
+            .args(args)
+            .env("SEABOX_SUDO_COMMAND", engine)
+            .env("SEABOX_FAKE_LOG", self.log())
+            .env("XDG_CONFIG_HOME", self.dir.join("config"))
+            .env("XDG_DATA_HOME", self.data_dir())
+            .stdin(Stdio::null());
+        command
+    }
+
+    /// Run the seabox binary, for tests that set environment variables or check its output
+    fn run_binary(&self, args: &[&str], env: &[(&str, &str)]) -> Output {
+        self.binary(args)
+            .envs(env.iter().copied())
+            .output()
+            .unwrap()
+    }
+
+    /// PATH with a fake rootless podman first, which records the first two arguments of its
+    /// invocations to rootless.log.
+    /// Rootless podman is found on PATH rather than run through the sudo command
+    fn rootless_path(&self) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let engine = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fake-engine.sh");
+        let bin = self.dir.join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let shim = bin.join("podman");
+        std::fs::write(
+            &shim,
+            format!(
+                "#!/bin/sh\necho \"$1 $2\" >>{}\nSEABOX_FAKE_ROOTLESS=1 exec {} podman \"$@\"\n",
+                self.dir.join("rootless.log").display(),
+                engine.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&shim, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        format!(
+            "{}:{}",
+            bin.display(),
+            std::env::var("PATH").unwrap_or_default()
+        )
+    }
+
+    /// Run the seabox binary with its stderr on a terminal, returning what it wrote there
+    fn run_binary_on_terminal(&self, args: &[&str]) -> String {
+        use std::io::Read;
+
+        let pty = nix::pty::openpty(None, None).unwrap();
+        {
+            // The command holds on to the terminal until it's dropped
+            let mut command = self.binary(args);
+            command.stdout(Stdio::null()).stderr(Stdio::from(pty.slave));
+            command.status().unwrap();
+        }
+
+        // Reading fails once the terminal is closed and everything was read
+        let mut stderr = vec![];
+        let _ = std::fs::File::from(pty.master).read_to_end(&mut stderr);
+        String::from_utf8_lossy(&stderr).to_string()
+    }
+
+    /// Arguments of every engine invocation, in order, without the sudo command
+    fn commands(&self) -> Vec<Vec<String>> {
+        let log = std::fs::read_to_string(self.log()).unwrap_or_default();
+
+        log.split('\x1e')
+            .filter(|x| !x.is_empty())
+            .map(|invocation| {
+                invocation
+                    .strip_suffix('\x1f')
+                    .unwrap_or(invocation)
+                    .split('\x1f')
+                    .map(String::from)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn contains_pair(command: &[String], flag: &str, value: &str) -> bool {
+    command.windows(2).any(|w| w[0] == flag && w[1] == value)
+}
+
+#[test]
+fn list_filters_by_label() {
+    let harness = Harness::new("list");
+
+    harness.run(&["ls"]).unwrap();
+
+    assert_eq!(
+        harness.commands(),
+        vec![vec![
+            "podman",
+            "ps",
+            "--all",
+            "--filter",
+            "label=seabox=true"
+        ]]
+    );
+}
+
+#[test]
+fn list_all_stores_merges_rootful_and_rootless() {
+    let harness = Harness::new("list_all_stores");
+    let path = harness.rootless_path();
+
+    let output = harness.run_binary(&["ls", "--all-stores", "--json"], &[("PATH", &path)]);
+    assert!(output.status.success());
+    let listed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(listed.as_array().unwrap().len(), 2);
+    assert_eq!(listed[0]["Names"][0], "dev");
+    assert_eq!(listed[0]["Store"], "rootful");
+    assert_eq!(listed[1]["Names"][0], "scratch");
+    assert_eq!(listed[1]["Store"], "rootless");
+
+    let output = harness.run_binary(&["ls", "--all-stores"], &[("PATH", &path)]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("NAME"));
+    assert!(lines[2].starts_with("scratch  rootless"));
+}
+
+#[test]
+fn list_names_only_prints_bare_names() {
+    let harness = Harness::new("list_names");
+
+    let output = harness.run_binary(&["ls", "-q"], &[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "dev\n");
+    assert_eq!(
+        harness.commands(),
+        vec![vec![
+            "podman",
+            "ps",
+            "--all",
+            "--filter",
+            "label=seabox=true",
+            "--format",
+            "{{.Names}}"
+        ]]
+    );
+}
+
+#[test]
+fn list_images_groups_boxes_by_image() {
+    let harness = Harness::new("list_images");
+
+    let output = harness.run_binary(&["ls", "--images", "--json"], &[]);
+    assert!(output.status.success());
+
+    let images: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(images[0]["Image"], "docker.io/library/fake:latest");
+    assert_eq!(images[0]["Boxes"], 1);
+    assert_eq!(images[0]["Names"][0], "dev");
+    assert_eq!(images[0]["Size"], 77_800_000);
+
+    let output = harness.run_binary(&["ls", "--images"], &[]);
+    let table = String::from_utf8_lossy(&output.stdout);
+    assert!(table.starts_with("IMAGE"));
+    assert!(table.contains("77.8 MB"));
+}
+
+#[test]
+fn doctor_reports_checks_as_json() {
+    let harness = Harness::new("doctor");
+
+    let output = harness.run_binary(&["doctor", "--json"], &[]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let checks = report["checks"].as_array().unwrap();
+
+    assert_eq!(checks[0]["check"], "engine");
+    assert_eq!(checks[0]["status"], "ok");
+    assert_eq!(checks[0]["detail"], "podman 5.0.0");
+
+    // Overall success follows the checks, the exit code too
+    let failed = checks.iter().any(|x| x["status"] == "fail");
+    assert_eq!(report["ok"], !failed);
+    assert_eq!(output.status.success(), !failed);
+
+    let output = harness.run_binary(&["doctor"], &[]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("engine: podman 5.0.0"));
+}
+
+#[test]
+fn list_without_boxes_prints_notice_or_empty_array() {
+    let harness = Harness::new("list_empty");
+
+    let output = harness.run_binary(&["ls", "--json"], &[]);
+    let listed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(listed[0]["Names"][0], "dev");
+
+    std::fs::write(harness.dir.join("no-containers"), "").unwrap();
+
+    let output = harness.run_binary(&["ls", "--json"], &[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "[]");
+
+    let output = harness.run_binary(&["ls"], &[]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr).trim(),
+        "No seabox containers found"
+    );
+}
+
+#[test]
+fn restart_all_restarts_every_listed_box() {
+    let harness = Harness::new("restart_all");
+
+    harness.run(&["restart", "--all"]).unwrap();
+
+    let commands = harness.commands();
+    assert_eq!(commands.len(), 3);
+    assert!(contains_pair(&commands[0], "--format", "{{.Names}}"));
+    assert_eq!(commands[1], vec!["podman", "stop", "dev"]);
+    assert_eq!(commands[2], vec!["podman", "start", "dev"]);
+}
+
+#[test]
+fn up_creates_boxes_of_file_and_down_removes_them() {
+    let harness = Harness::new("up_down");
+    let file = harness.dir.join("boxes.toml");
+    std::fs::write(
+        &file,
+        r#"
+[[box]]
+name = "db"
+image = "docker.io/library/fake:latest"
+no_hosts = true
+
+[[box]]
+name = "app"
+image = "docker.io/library/fake:latest"
+no_password = true
+"#,
+    )
+    .unwrap();
+    let file = file.to_str().unwrap();
+
+    harness.run(&["up", file]).unwrap();
+    let created = |commands: &[Vec<String>]| -> Vec<String> {
+        commands
+            .iter()
+            .filter(|c| c[..3] == ["podman", "run", "--label"])
+            .map(|c| c[c.iter().position(|x| x == "--name").unwrap() + 1].clone())
+            .collect()
+    };
+    let commands = harness.commands();
+    assert_eq!(created(&commands), ["db", "app"]);
+    let db = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(db.contains(&"--no-hosts".to_string()));
+    // Boxes are set up, but not entered
+    assert!(
+        commands
+            .iter()
+            .filter(|c| c[..3] == ["podman", "exec", "-it"])
+            .all(|c| contains_pair(c, "--user", "root"))
+    );
+
+    // Existing boxes are left alone
+    harness.run(&["up", file]).unwrap();
+    assert_eq!(created(&harness.commands()), ["db", "app"]);
+
+    harness.run(&["down", file]).unwrap();
+    let removed: Vec<String> = harness
+        .commands()
+        .iter()
+        .filter(|c| c[..3] == ["podman", "container", "rm"])
+        .map(|c| c.last().unwrap().clone())
+        .collect();
+    assert_eq!(removed, ["app", "db"]);
+
+    std::fs::write(
+        harness.dir.join("typo.toml"),
+        "[[box]]\nname = \"x\"\nimag = \"y\"\n",
+    )
+    .unwrap();
+    let result = harness.run(&["up", harness.dir.join("typo.toml").to_str().unwrap()]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn up_resolves_boxes_like_create() {
+    let harness = Harness::new("up_layers");
+    let config_dir = harness.dir.join("config/seabox");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("seabox.toml"),
+        "[presets.net]\nnetwork = \"bridge\"\n",
+    )
+    .unwrap();
+    let file = harness.dir.join("boxes.toml");
+    std::fs::write(
+        &file,
+        r#"
+[[box]]
+name = "db"
+image = "docker.io/library/fake:latest"
+preset = "net"
+
+[[box]]
+name = "app"
+image = "docker.io/library/fake:latest"
+"#,
+    )
+    .unwrap();
+
+    let output = harness.run_binary(
+        &["up", file.to_str().unwrap()],
+        &[("SEABOX_HOSTNAME", "from-env")],
+    );
+    assert!(output.status.success());
+
+    let commands = harness.commands();
+    let run_of = |name: &str| {
+        commands
+            .iter()
+            .find(|c| c[..3] == ["podman", "run", "--label"] && contains_pair(c, "--name", name))
+            .unwrap()
+    };
+    assert!(contains_pair(run_of("db"), "--network", "bridge"));
+    assert!(!contains_pair(run_of("app"), "--network", "bridge"));
+    for name in ["db", "app"] {
+        assert!(contains_pair(run_of(name), "--hostname", "from-env"));
+    }
+}
+
+#[test]
+fn trace_prints_commands_and_exit_status_as_they_run() {
+    let harness = Harness::new("trace");
+
+    let output = harness.run_binary(&["restart", "--trace", "dev"], &[]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let trace: Vec<&str> = stderr
+        .lines()
+        .filter(|x| x.starts_with("[trace] "))
+        .collect();
+    assert!(trace.len() >= 4);
+    assert!(trace.iter().any(|x| x.ends_with("podman stop dev")));
+    assert!(trace.iter().any(|x| x.ends_with("podman start dev")));
+    assert!(trace.contains(&"[trace] exit status: 0"));
+    // Unlike a dry run, the commands ran
+    assert_eq!(harness.commands().len(), trace.len() / 2);
+
+    let output = harness.run_binary(&["restart", "dev"], &[("SEABOX_TRACE", "1")]);
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("[trace] ")
+    );
+
+    let output = harness.run_binary(&["restart", "dev"], &[]);
+    assert!(
+        !String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("[trace] ")
+    );
+}
+
+#[test]
+fn all_conflicts_with_names() {
+    let result = Cli::try_parse_from(["seabox", "stop", "--all", "dev"]);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn engine_env_is_set_on_engine_process() {
+    let harness = Harness::new("engine_env");
+
+    harness
+        .run(&[
+            "ls",
+            "--engine-env",
+            "CONTAINERS_STORAGE_CONF=/opt/storage.conf",
+        ])
+        .unwrap();
+
+    let env = std::fs::read_to_string(harness.dir.join("engine-env")).unwrap();
+    assert_eq!(env, "CONTAINERS_STORAGE_CONF=/opt/storage.conf\n");
+    // Not passed to podman as an argument
+    assert_eq!(harness.commands()[0].len(), 5);
+}
+
+#[test]
+fn engine_env_requires_key_value() {
+    let harness = Harness::new("engine_env_invalid");
+
+    let result = harness.run(&["ls", "--engine-env", "CONTAINERS_STORAGE_CONF"]);
+
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+    assert!(harness.commands().is_empty());
+}
+
+#[test]
+fn remove_volumes_adds_flag_to_delete_command() {
+    let harness = Harness::new("remove_volumes");
+
+    harness.run(&["rm", "-v", "dev", "other"]).unwrap();
+
+    let commands = harness.commands();
+    assert_eq!(
+        commands[1],
+        vec!["podman", "container", "rm", "--force", "--volumes", "dev"]
+    );
+    assert_eq!(
+        commands[3],
+        vec!["podman", "container", "rm", "--force", "--volumes", "other"]
+    );
+}
+
+#[test]
+fn enter_mkdir_creates_workdir_for_user() {
+    let harness = Harness::new("enter_mkdir");
+
+    harness
+        .run(&["enter", "-w", "/work/fresh", "--mkdir", "dev"])
+        .unwrap();
+
+    let commands = harness.commands();
+    let mkdir = commands
+        .iter()
+        .position(|c| c[..4] == ["podman", "exec", "--user", "root"])
+        .unwrap();
+    assert_eq!(commands[mkdir][9..], ["/work/fresh", "1000"]);
+
+    let exec = commands
+        .iter()
+        .position(|c| c[..3] == ["podman", "exec", "-it"])
+        .unwrap();
+    assert!(mkdir < exec);
+    assert!(contains_pair(&commands[exec], "-w", "/work/fresh"));
+
+    let result = harness.run(&["enter", "-w", "relative", "dev"]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn enter_path_lands_in_mount_subdirectory() {
+    let harness = Harness::new("enter_path");
+
+    harness
+        .run(&["enter", "--path", "/src/lib/", "--mkdir", "dev"])
+        .unwrap();
+
+    let commands = harness.commands();
+    let mkdir = commands
+        .iter()
+        .find(|c| c[..4] == ["podman", "exec", "--user", "root"])
+        .unwrap();
+    assert_eq!(mkdir[9..], ["/mount/src/lib", "1000"]);
+
+    let exec = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "exec", "-it"])
+        .unwrap();
+    assert!(contains_pair(exec, "-w", "/mount/src/lib"));
+
+    let result = Cli::try_parse_from(["seabox", "enter", "-w", "/work", "--path", "src", "dev"]);
+    assert!(result.is_err());
+    let result = Cli::try_parse_from(["seabox", "enter", "--mkdir", "dev"]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn enter_no_workdir_omits_working_directory() {
+    let harness = Harness::new("enter_no_workdir");
+
+    harness.run(&["enter", "--no-workdir", "dev"]).unwrap();
+
+    let commands = harness.commands();
+    let exec = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "exec", "-it"])
+        .unwrap();
+    assert!(!exec.contains(&"-w".to_string()));
+    assert!(exec.contains(&"dev".to_string()));
+
+    let result = Cli::try_parse_from(["seabox", "enter", "--no-workdir", "-w", "/work", "dev"]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn enter_execs_into_existing_container() {
+    let harness = Harness::new("enter");
+
+    harness.run(&["enter", "dev"]).unwrap();
+
+    let commands = harness.commands();
+    assert_eq!(commands.len(), 2);
+    assert_eq!(commands[0], vec!["podman", "container", "inspect", "dev"]);
+
+    let exec = &commands[1];
+    assert_eq!(exec[..3], ["podman", "exec", "-it"]);
+    assert!(contains_pair(exec, "--user", "1000:"));
+    // The current directory is outside of the mounted host directory
+    assert!(contains_pair(exec, "-w", "/mount/"));
+    assert!(contains_pair(exec, "dev", "/bin/sh"));
+}
+
+#[test]
+fn enter_propagates_exit_code_when_exec_returns() {
+    let harness = Harness::new("enter_exit_code");
+    std::fs::write(harness.dir.join("exec-exit-code"), "5").unwrap();
+
+    // The test runner can't replace the process, like platforms without exec
+    let result = harness.run(&["enter", "dev"]);
+
+    assert!(matches!(result, Err(SeaboxError::SessionFailed(5))));
+    assert_eq!(result.unwrap_err().exit_code(), 5);
+}
+
+#[test]
+fn enter_root_enters_as_root_and_conflicts_with_user() {
+    let harness = Harness::new("enter_root");
+
+    harness.run(&["enter", "--root", "dev"]).unwrap();
+
+    let commands = harness.commands();
+    assert!(contains_pair(&commands[1], "--user", "root"));
+
+    let result = Cli::try_parse_from(["seabox", "enter", "--root", "--user", "user", "dev"]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn enter_user_is_validated_against_container() {
+    let harness = Harness::new("enter_user");
+
+    harness.run(&["enter", "--user", "ubuntu", "dev"]).unwrap();
+    assert!(contains_pair(
+        harness.commands().last().unwrap(),
+        "--user",
+        "ubuntu"
+    ));
+
+    // Numeric ids are passed on without looking them up
+    std::fs::remove_file(harness.log()).unwrap();
+    harness
+        .run(&["enter", "--user", "1234:1234", "dev"])
+        .unwrap();
+    assert!(
+        harness
+            .commands()
+            .iter()
+            .all(|c| c.last().unwrap() != "/etc/passwd")
+    );
+
+    let result = harness.run(&["enter", "--user", "ubunt", "dev"]);
+    assert!(
+        matches!(result, Err(SeaboxError::InvalidArgument(message)) if message.ends_with("available users: root, ubuntu"))
+    );
+
+    // A uid with an empty group enters with the user's primary group
+    harness.run(&["enter", "--user", "1000:", "dev"]).unwrap();
+    assert!(contains_pair(
+        harness.commands().last().unwrap(),
+        "--user",
+        "1000:"
+    ));
+
+    let result = harness.run(&["enter", "--user", ":1000", "dev"]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn detach_keys_are_passed_to_exec_and_temp() {
+    let harness = Harness::new("detach_keys");
+
+    harness
+        .run(&["enter", "--detach-keys", "ctrl-x,ctrl-y", "dev"])
+        .unwrap();
+    harness.run(&["enter", "--detach-keys=", "dev"]).unwrap();
+    harness
+        .run(&[
+            "tmp",
+            "--detach-keys",
+            "ctrl-a",
+            "-i",
+            "docker.io/library/fake:latest",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let execs: Vec<_> = commands
+        .iter()
+        .filter(|c| c[..2] == ["podman", "exec"])
+        .collect();
+    assert!(execs[0].contains(&"--detach-keys=ctrl-x,ctrl-y".to_string()));
+    assert!(execs[1].contains(&"--detach-keys=".to_string()));
+
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(run.contains(&"--detach-keys=ctrl-a".to_string()));
+}
+
+#[cfg(unix)]
+#[test]
+fn enter_lands_in_subdirectory_through_symlinked_mount_source() {
+    let harness = Harness::new("enter_symlink");
+    let real = harness.dir.join("real");
+    let link = harness.dir.join("link");
+    std::fs::create_dir_all(real.join("sub")).unwrap();
+    std::os::unix::fs::symlink(&real, &link).unwrap();
+
+    // A container whose mount source was recorded through the symlink
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/container-dev.json");
+    let info = std::fs::read_to_string(fixture)
+        .unwrap()
+        .replace("/nonexistent/project", link.to_str().unwrap());
+    std::fs::write(harness.dir.join("container-linked.json"), info).unwrap();
+
+    let status = harness
+        .binary(&["enter", "linked"])
+        .current_dir(real.join("sub"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert!(contains_pair(&harness.commands()[1], "-w", "/mount/sub"));
+}
+
+#[test]
+fn which_fails_for_missing_container() {
+    let harness = Harness::new("which_missing");
+
+    let result = harness.run(&["which", "missing", "--field", "mount"]);
+
+    assert!(matches!(result, Err(SeaboxError::ContainerNotFound { .. })));
+}
+
+#[test]
+fn enter_sets_session_environment() {
+    let harness = Harness::new("enter_env");
+    let path = std::env::var("PATH").unwrap();
+
+    harness
+        .run(&["enter", "--env", "DEBUG=1", "-e", "PATH", "dev"])
+        .unwrap();
+
+    let exec = &harness.commands()[1];
+    assert!(contains_pair(exec, "-e", "DEBUG=1"));
+    assert!(contains_pair(exec, "-e", &format!("PATH={path}")));
+    // Options of podman exec come before the container name
+    assert!(contains_pair(exec, "dev", "/bin/sh"));
+
+    let result = harness.run(&["enter", "--env", "1BAD=x", "dev"]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn enter_uses_resolved_uid_before_container_user() {
+    let harness = Harness::new("enter_nouser");
+
+    harness.run(&["enter", "nouser"]).unwrap();
+
+    let exec = &harness.commands()[1];
+    assert_eq!(exec[..3], ["podman", "exec", "-it"]);
+    assert!(contains_pair(exec, "--user", "1000:"));
+
+    // The resolved uid wins over the user the container runs as
+    std::fs::write(
+        harness.dir.join("container-other.json"),
+        r#"[{"Id": "1a2b", "ImageName": "docker.io/library/fake:latest", "Mounts": [],
+            "State": {"Running": true}, "Config": {"User": "2000:",
+            "Labels": {"SEABOX_RESOLVED_UID": "1001", "SEABOX_RESOLVED_GID": "1002"}}}]"#,
+    )
+    .unwrap();
+    harness.run(&["enter", "other"]).unwrap();
+    assert!(contains_pair(
+        harness.commands().last().unwrap(),
+        "--user",
+        "1001:"
+    ));
+
+    // Containers without them are entered as their user
+    std::fs::write(
+        harness.dir.join("container-other.json"),
+        r#"[{"Id": "1a2b", "ImageName": "docker.io/library/fake:latest", "Mounts": [],
+            "State": {"Running": true}, "Config": {"User": "2000:", "Labels": {}}}]"#,
+    )
+    .unwrap();
+    harness.run(&["enter", "other"]).unwrap();
+    assert!(contains_pair(
+        harness.commands().last().unwrap(),
+        "--user",
+        "2000:"
+    ));
+}
+
+#[test]
+fn enter_handles_empty_and_partial_inspect_output() {
+    let harness = Harness::new("enter_partial_inspect");
+
+    // Podman can report nothing for a container that was only just created
+    std::fs::write(harness.dir.join("container-fresh.json"), "[]").unwrap();
+    let result = harness.run(&["enter", "fresh"]);
+    assert!(
+        matches!(result, Err(SeaboxError::CommandFailed(message)) if message.contains("isn't inspectable yet"))
+    );
+    assert!(harness.commands().len() > 1);
+
+    // Or leave out its mounts and config
+    std::fs::write(
+        harness.dir.join("container-fresh.json"),
+        r#"[{"ImageName": "docker.io/library/fake:latest", "State": {"Running": true}}]"#,
+    )
+    .unwrap();
+    std::fs::remove_file(harness.log()).unwrap();
+    harness.run(&["enter", "fresh"]).unwrap();
+    assert!(contains_pair(
+        harness.commands().last().unwrap(),
+        "--user",
+        "user"
+    ));
+}
+
+#[test]
+fn enter_missing_container_fails() {
+    let harness = Harness::new("enter_missing");
+
+    let result = harness.run(&["enter", "missing"]);
+
+    assert!(matches!(
+        result,
+        Err(SeaboxError::ContainerNotFound { name, suggestions, .. }) if name == "missing" && suggestions.is_empty()
+    ));
+    // Inspecting the container, then listing boxes for suggestions
+    assert_eq!(harness.commands().len(), 2);
+}
+
+#[test]
+fn enter_suggests_similar_box_names() {
+    let harness = Harness::new("enter_typo");
+
+    let result = harness.run(&["enter", "dve"]);
+
+    assert!(matches!(
+        &result,
+        Err(SeaboxError::ContainerNotFound { suggestions, .. }) if suggestions == &["dev"]
+    ));
+    assert!(
+        result
+            .unwrap_err()
+            .to_string()
+            .contains("Did you mean 'dev'?")
+    );
+}
+
+#[test]
+fn remove_suggests_similar_box_names_only_for_missing_boxes() {
+    let harness = Harness::new("remove_typo");
+    std::fs::write(harness.dir.join("rm-exit-code"), "1").unwrap();
+
+    let output = harness.run_binary(&["rm", "dve"], &[]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Did you mean 'dev'?"));
+
+    // A box that exists but couldn't be removed isn't a typo
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/container-dev.json");
+    std::fs::copy(fixture, harness.dir.join("container-dve.json")).unwrap();
+    let output = harness.run_binary(&["rm", "dve"], &[]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Did you mean"));
+}
+
+#[test]
+fn create_matches_image_user_and_runs_init_script() {
+    let harness = Harness::new("create");
+
+    harness
+        .run(&["create", "-i", "docker.io/library/fake:latest", "newbox"])
+        .unwrap();
+
+    let commands = harness.commands();
+    let prefixes: Vec<&[String]> = commands.iter().map(|c| &c[..3]).collect();
+    assert_eq!(
+        prefixes,
+        vec![
+            ["podman", "image", "inspect"],
+            ["podman", "run", "--rm"],
+            ["podman", "container", "inspect"],
+            ["podman", "run", "--label"],
+            ["podman", "image", "inspect"],
+            ["podman", "container", "inspect"],
+            ["podman", "exec", "-it"],
+            ["podman", "container", "inspect"],
+            ["podman", "exec", "-it"],
+        ]
+    );
+
+    // uid 1000 is taken from the image's /etc/passwd
+    let run = &commands[3];
+    assert!(contains_pair(run, "--label", "SEABOX_RESOLVED_UID=1000"));
+    assert!(contains_pair(run, "-u", "1000:"));
+    assert!(contains_pair(run, "--name", "newbox"));
+    assert_eq!(run.last().unwrap(), "/bin/sh");
+
+    // The init script sets up the user as root, then the box is entered as the user
+    let setup = &commands[6];
+    assert!(contains_pair(setup, "--user", "root"));
+    assert!(contains_pair(setup, "newbox", "/bin/sh"));
+    let exec = &commands[8];
+    assert!(contains_pair(exec, "--user", "1000:"));
+}
+
+#[test]
+fn create_no_enter_only_sets_up_the_container() {
+    let harness = Harness::new("no_enter");
+
+    harness
+        .run(&[
+            "create",
+            "--no-enter",
+            "-i",
+            "docker.io/library/fake:latest",
+            "detached",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let execs: Vec<_> = commands
+        .iter()
+        .filter(|c| c[..2] == ["podman", "exec"])
+        .collect();
+    assert_eq!(execs.len(), 1);
+    assert!(contains_pair(execs[0], "--user", "root"));
+
+    // A failing init script is reported instead of entering a half set up box
+    std::fs::remove_file(harness.log()).unwrap();
+    std::fs::write(harness.dir.join("exec-exit-code"), "1").unwrap();
+
+    let result = harness.run(&["create", "-i", "docker.io/library/fake:latest", "broken"]);
+    assert!(matches!(result, Err(SeaboxError::CommandFailed(_))));
+    assert_eq!(
+        harness
+            .commands()
+            .iter()
+            .filter(|c| c[..2] == ["podman", "exec"])
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn create_mounts_into_home_of_image_user() {
+    let harness = Harness::new("mount_into_home");
+    let project = harness.dir.join("project");
+    std::fs::create_dir(&project).unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "--mount-into-home",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-d",
+            project.to_str().unwrap(),
+            "homebox",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+
+    // /home/ubuntu is the home of uid 1000 in the image's /etc/passwd
+    assert!(contains_pair(
+        run,
+        "--label",
+        "SEABOX_MOUNT_TARGET=/home/ubuntu/project"
+    ));
+    assert!(contains_pair(run, "-w", "/home/ubuntu/project/"));
+    assert!(run.iter().any(|x| x.starts_with(&format!(
+        "type=bind,source={},destination=/home/ubuntu/project/,",
+        project.canonicalize().unwrap().to_str().unwrap()
+    ))));
+}
+
+#[test]
+fn create_mounts_at_host_path() {
+    let harness = Harness::new("mount_at_host_path");
+    let project = harness.dir.join("project");
+    std::fs::create_dir(&project).unwrap();
+    let project = project.canonicalize().unwrap();
+    let project = project.to_str().unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "--mount-at-host-path",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-d",
+            project,
+            "samepath",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+
+    assert!(contains_pair(
+        run,
+        "--label",
+        &format!("SEABOX_MOUNT_TARGET={project}")
+    ));
+    assert!(contains_pair(run, "-w", &format!("{project}/")));
+    assert!(run.iter().any(|x| x.starts_with(&format!(
+        "type=bind,source={project},destination={project}/,"
+    ))));
+}
+
+#[test]
+fn single_path_volume_mounts_to_same_path() {
+    let harness = Harness::new("same_path_volume");
+    let data = harness.dir.canonicalize().unwrap();
+    let data = data.to_str().unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-v",
+            data,
+            "-v",
+            "/tmp:/host-tmp",
+            "volumes",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+
+    let prefix = format!("type=bind,source={data},destination={data},idmap=");
+    assert!(run.iter().any(|x| x.starts_with(&prefix)));
+    assert!(
+        run.iter()
+            .any(|x| x.starts_with("type=bind,source=/tmp,destination=/host-tmp,idmap="))
+    );
+}
+
+#[test]
+fn volumes_env_adds_to_cli_volumes() {
+    let harness = Harness::new("volumes_env");
+
+    let output = harness.run_binary(
+        &[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-v",
+            "/tmp:/cli-tmp",
+            "volumes",
+        ],
+        &[("SEABOX_VOLUMES", "/tmp:/env-tmp; ;/tmp:/other-tmp")],
+    );
+    assert!(output.status.success());
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+
+    for target in ["/cli-tmp", "/env-tmp", "/other-tmp"] {
+        let prefix = format!("type=bind,source=/tmp,destination={target},");
+        assert!(run.iter().any(|x| x.starts_with(&prefix)), "{target}");
+    }
+}
+
+#[test]
+fn mount_specs_pass_through_with_idmap_for_binds() {
+    let harness = Harness::new("mount_spec");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--mount",
+            "type=bind,source=/tmp,destination=/data,ro=true",
+            "--mount",
+            "type=bind,source=/tmp,destination=/own,idmap",
+            "--mount",
+            "type=tmpfs,destination=/scratch",
+            "mounts",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+
+    assert!(
+        run.iter().any(|x| {
+            x.starts_with("type=bind,source=/tmp,destination=/data,ro=true,idmap=uids=")
+        })
+    );
+    assert!(contains_pair(
+        run,
+        "--mount",
+        "type=bind,source=/tmp,destination=/own,idmap"
+    ));
+    assert!(contains_pair(
+        run,
+        "--mount",
+        "type=tmpfs,destination=/scratch"
+    ));
+}
+
+#[test]
+fn relabel_options_are_added_to_mounts() {
+    let harness = Harness::new("relabel");
+    let data = harness.dir.canonicalize().unwrap();
+    let data = data.to_str().unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-d",
+            data,
+            "--relabel",
+            "private",
+            "-v",
+            "/tmp:/shared:z",
+            "-v",
+            "/tmp:Z",
+            "relabelled",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    let mounts: Vec<&String> = run.iter().filter(|x| x.starts_with("type=bind,")).collect();
+
+    assert!(mounts[0].starts_with(&format!("type=bind,source={data},destination=/mount/,")));
+    assert!(mounts[0].ends_with(",relabel=private"));
+    assert!(mounts[1].starts_with("type=bind,source=/tmp,destination=/shared,"));
+    assert!(mounts[1].ends_with(",relabel=shared"));
+    assert!(mounts[2].starts_with("type=bind,source=/tmp,destination=/tmp,"));
+    assert!(mounts[2].ends_with(",relabel=private"));
+}
+
+#[test]
+fn volumes_can_override_idmap() {
+    let harness = Harness::new("volume_idmap");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-v",
+            "/tmp:/cache:idmap=0-0-1",
+            "-v",
+            "/tmp:/plain:Z:idmap=none",
+            "-v",
+            "/tmp:/shared",
+            "mapped",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    let mounts: Vec<&String> = run.iter().filter(|x| x.starts_with("type=bind,")).collect();
+
+    assert_eq!(
+        mounts[0],
+        "type=bind,source=/tmp,destination=/cache,idmap=uids=0-0-1;gids=0-0-1"
+    );
+    assert_eq!(
+        mounts[1],
+        "type=bind,source=/tmp,destination=/plain,relabel=private"
+    );
+    // Other volumes keep the idmap of the container user
+    assert!(mounts[2].contains(",idmap=uids="));
+    assert!(!mounts[2].contains("idmap=uids=0-0-1;"));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "-v",
+        "/tmp:/cache:idmap=0-0",
+        "malformed",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn create_reports_unsupported_idmap() {
+    let harness = Harness::new("idmap_unsupported");
+    std::fs::write(harness.dir.join("no-idmap-support"), "").unwrap();
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "-d",
+        harness.dir.to_str().unwrap(),
+        "noidmap",
+    ]);
+
+    assert!(matches!(result, Err(SeaboxError::IdmapUnsupported(_))));
+}
+
+#[test]
+fn create_auto_fallback_retries_without_idmap() {
+    let harness = Harness::new("idmap_fallback");
+    std::fs::write(harness.dir.join("no-idmap-support"), "").unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "--auto-fallback",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-d",
+            harness.dir.to_str().unwrap(),
+            "fallback",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let runs: Vec<&Vec<String>> = commands
+        .iter()
+        .filter(|c| c[..3] == ["podman", "run", "--label"])
+        .collect();
+
+    assert_eq!(runs.len(), 2);
+    assert!(runs[0].iter().any(|x| x.contains("idmap=")));
+    assert!(!runs[1].iter().any(|x| x.contains("idmap=")));
+    assert!(
+        commands
+            .iter()
+            .any(|c| *c == ["podman", "container", "rm", "--force", "fallback"])
+    );
+}
+
+#[test]
+fn temp_auto_fallback_retries_without_idmap() {
+    let harness = Harness::new("temp_idmap_fallback");
+    std::fs::write(harness.dir.join("no-idmap-support"), "").unwrap();
+
+    harness
+        .run(&[
+            "tmp",
+            "--auto-fallback",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-d",
+            harness.dir.to_str().unwrap(),
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let runs: Vec<&Vec<String>> = commands
+        .iter()
+        .filter(|c| c[..3] == ["podman", "run", "--label"])
+        .collect();
+
+    assert_eq!(runs.len(), 2);
+    assert!(runs[0].iter().any(|x| x.contains("idmap=")));
+    assert!(!runs[1].iter().any(|x| x.contains("idmap=")));
+}
+
+#[test]
+fn create_resets_entrypoint_and_still_runs_init_script() {
+    let harness = Harness::new("entrypoint");
+
+    harness
+        .run(&[
+            "create",
+            "--entrypoint",
+            "",
+            "-i",
+            "docker.io/library/fake:latest",
+            "entry",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+
+    // The entrypoint reset comes before the image, the command after it
+    let entrypoint = run.iter().position(|x| x == "--entrypoint=").unwrap();
+    let image = run
+        .iter()
+        .position(|x| x == "docker.io/library/fake:latest")
+        .unwrap();
+    assert!(entrypoint < image);
+    assert_eq!(run[image + 1..], ["/bin/sh"]);
+
+    // The init script is run with exec, independent of the entrypoint
+    let exec = commands
+        .iter()
+        .find(|c| c[..2] == ["podman", "exec"])
+        .unwrap();
+    assert_eq!(exec[..3], ["podman", "exec", "-it"]);
+    assert!(contains_pair(exec, "--user", "root"));
+    assert!(contains_pair(exec, "entry", "/bin/sh"));
+}
+
+#[test]
+fn temp_script_runs_after_user_setup_and_propagates_exit_code() {
+    let harness = Harness::new("temp_script");
+    let script = harness.dir.join("script.sh");
+    std::fs::write(&script, "cargo test\n").unwrap();
+    std::fs::write(harness.dir.join("run-exit-code"), "3").unwrap();
+
+    let result = harness.run(&[
+        "tmp",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--script",
+        script.to_str().unwrap(),
+    ]);
+
+    assert!(matches!(result, Err(SeaboxError::ScriptFailed(3))));
+    assert_eq!(result.unwrap_err().exit_code(), 3);
+
+    let commands = harness.commands();
+    let run = commands.last().unwrap();
+    assert_eq!(run[..3], ["podman", "run", "--label"]);
+    assert!(run.contains(&"--rm".to_string()));
+
+    // The script is passed to the init script, which runs it as the user
+    let n = run.len();
+    assert_eq!(run[n - 5..n - 3], ["/bin/sh", "-c"]);
+    assert!(run[n - 3].contains("PARAM_SCRIPT"));
+    assert_eq!(run[n - 2..], ["seabox", "cargo test\n"]);
+}
+
+#[test]
+fn temp_command_from_config_runs_unless_shell_is_given() {
+    let harness = Harness::new("temp_command");
+    let env = [("SEABOX_TEMP_COMMAND", r#"["cargo", "test", "--all"]"#)];
+
+    let output = harness.run_binary(&["tmp", "-i", "docker.io/library/fake:latest"], &env);
+    assert!(output.status.success());
+
+    let commands = harness.commands();
+    let run = commands.last().unwrap();
+    assert_eq!(run[run.len() - 2..], ["seabox", "cargo test --all"]);
+
+    let output = harness.run_binary(
+        &[
+            "tmp",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-s",
+            "/bin/zsh",
+        ],
+        &env,
+    );
+    assert!(output.status.success());
+
+    let commands = harness.commands();
+    let run = commands.last().unwrap();
+    assert!(run.iter().all(|x| x != "cargo test --all"));
+}
+
+#[test]
+fn passwd_entry_is_only_generated_when_requested() {
+    let harness = Harness::new("create_passwd");
+    let image = "docker.io/library/fake:latest";
+
+    harness
+        .run(&["create", "--no-enter", "-i", image, "dev-default"])
+        .unwrap();
+    harness
+        .run(&[
+            "create",
+            "--no-enter",
+            "--passwd",
+            "-i",
+            image,
+            "dev-passwd",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let passwd: Vec<bool> = commands
+        .iter()
+        .filter(|c| c[..3] == ["podman", "run", "--label"])
+        .map(|c| {
+            let flags: Vec<&String> = c.iter().filter(|a| a.starts_with("--passwd=")).collect();
+            assert_eq!(flags.len(), 1);
+            flags[0] == "--passwd=true"
+        })
+        .collect();
+    assert_eq!(passwd, [false, true]);
+}
+
+#[test]
+fn privileged_warning_is_shown_once_per_invocation() {
+    let harness = Harness::new("privileged_warning");
+    std::fs::write(harness.dir.join("no-idmap-support"), "").unwrap();
+    let image = "docker.io/library/fake:latest";
+    let dir = harness.dir.to_str().unwrap();
+
+    // Not again when retrying without idmap
+    let stderr = harness.run_binary_on_terminal(&[
+        "create",
+        "--auto-fallback",
+        "-i",
+        image,
+        "-d",
+        dir,
+        "fallback",
+    ]);
+    let runs = harness
+        .commands()
+        .into_iter()
+        .filter(|c| c[..3] == ["podman", "run", "--label"])
+        .count();
+    assert_eq!(runs, 2);
+    assert_eq!(
+        stderr.matches("Warning: 'fallback' is privileged").count(),
+        1
+    );
+
+    let stderr = harness.run_binary_on_terminal(&["tmp", "--privileged", "-i", image]);
+    assert_eq!(
+        stderr
+            .matches("Warning: this temp box is privileged")
+            .count(),
+        1
+    );
+
+    for args in [
+        &["create", "--dry-run", "-i", image, "planned"][..],
+        &["create", "--no-privileged-warning", "-i", image, "quiet"][..],
+        &["tmp", "-i", image][..],
+    ] {
+        let stderr = harness.run_binary_on_terminal(args);
+        assert!(!stderr.contains("is privileged"), "{args:?}: {stderr}");
+    }
+}
+
+#[test]
+fn temp_is_unprivileged_unless_requested() {
+    let harness = Harness::new("temp_privileged");
+    let image = "docker.io/library/fake:latest";
+
+    harness.run(&["tmp", "-i", image]).unwrap();
+    harness.run(&["tmp", "--privileged", "-i", image]).unwrap();
+    harness
+        .run(&["create", "-i", image, "dev-default"])
+        .unwrap();
+    harness
+        .run(&[
+            "create",
+            "--privileged=false",
+            "-i",
+            image,
+            "dev-unprivileged",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let privileged: Vec<bool> = commands
+        .iter()
+        .filter(|c| c[..3] == ["podman", "run", "--label"])
+        .map(|c| c.contains(&"--privileged".to_string()))
+        .collect();
+    assert_eq!(privileged, [false, true, true, false]);
+}
+
+#[test]
+fn named_temp_is_removed_if_it_lingers() {
+    let harness = Harness::new("temp_name");
+
+    harness
+        .run(&[
+            "tmp",
+            "--name",
+            "scratch",
+            "--stop-timeout",
+            "5",
+            "-i",
+            "docker.io/library/fake:latest",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .position(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(&commands[run], "--name", "scratch"));
+
+    // The fake engine ignores --rm, so the container lingers
+    assert_eq!(
+        commands[run + 1],
+        vec!["podman", "container", "inspect", "scratch"]
+    );
+    // It gets its own grace period to shut down before it is removed
+    assert!(contains_pair(&commands[run], "--stop-timeout", "5"));
+    assert_eq!(commands[run + 2], vec!["podman", "stop", "scratch"]);
+    assert_eq!(
+        commands[run + 3],
+        vec!["podman", "container", "rm", "--force", "scratch"]
+    );
+    assert_eq!(commands.len(), run + 4);
+}
+
+#[test]
+fn create_passes_ulimits_and_init_and_rejects_malformed_ulimits() {
+    let harness = Harness::new("ulimit");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--ulimit",
+            "nofile=65536:65536",
+            "--ulimit",
+            "core=-1",
+            "--ulimit",
+            "host",
+            "--init=true",
+            "limits",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--ulimit", "nofile=65536:65536"));
+    assert!(contains_pair(run, "--ulimit", "core=-1"));
+    assert!(contains_pair(run, "--ulimit", "host"));
+    // podman's init wraps our command, which is still the last argument
+    assert!(run.contains(&"--init".to_string()));
+    assert_eq!(run.last().unwrap(), "/bin/sh");
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--ulimit",
+        "nofile=lots",
+        "badlimits",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn stop_signal_replaces_immediate_stop_timeout() {
+    let harness = Harness::new("stop_signal");
+
+    harness
+        .run(&["create", "-i", "docker.io/library/fake:latest", "plain"])
+        .unwrap();
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--stop-signal",
+            "SIGINT",
+            "graceful",
+        ])
+        .unwrap();
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--stop-signal",
+            "SIGINT",
+            "--stop-timeout",
+            "30",
+            "patient",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let runs: Vec<_> = commands
+        .iter()
+        .filter(|c| c[..3] == ["podman", "run", "--label"])
+        .collect();
+    assert!(contains_pair(runs[0], "--stop-timeout", "0"));
+    assert!(!runs[0].contains(&"--stop-signal".to_string()));
+    assert!(contains_pair(runs[1], "--stop-signal", "SIGINT"));
+    assert!(contains_pair(runs[1], "--stop-timeout", "10"));
+    assert!(contains_pair(runs[2], "--stop-signal", "SIGINT"));
+    assert!(contains_pair(runs[2], "--stop-timeout", "30"));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--stop-signal",
+        "SIGNOPE",
+        "invalid",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+
+    // Stopping leaves the grace period to the container instead of killing it immediately
+    harness.run(&["stop", "graceful"]).unwrap();
+    let commands = harness.commands();
+    let stop = commands.last().unwrap();
+    assert_eq!(stop, &vec!["podman", "stop", "graceful"]);
+    assert!(!contains_pair(stop, "--time", "0"));
+}
+
+#[test]
+fn runtime_is_passed_and_unknown_runtime_reported() {
+    let harness = Harness::new("runtime");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--runtime",
+            "crun",
+            "crun",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--runtime", "crun"));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--runtime",
+        "missing-runtime",
+        "missing",
+    ]);
+    assert!(
+        matches!(result, Err(SeaboxError::CommandFailed(msg)) if msg.contains("OCI runtime 'missing-runtime' is not available"))
+    );
+}
+
+#[test]
+fn log_driver_and_options_are_passed() {
+    let harness = Harness::new("log_opt");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--log-driver",
+            "k8s-file",
+            "--log-opt",
+            "max-size=10m",
+            "--log-opt",
+            "max-file=3",
+            "logged",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--log-driver", "k8s-file"));
+    assert!(contains_pair(run, "--log-opt", "max-size=10m"));
+    assert!(contains_pair(run, "--log-opt", "max-file=3"));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--log-opt",
+        "max-size",
+        "malformed",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn hosts_from_config_and_cli_are_added() {
+    let harness = Harness::new("hosts");
+
+    // The config value is taken from the environment, like any other top-level key
+    let output = harness.run_binary(
+        &[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--add-host",
+            "artifacts.internal:fd00::5",
+            "hosts",
+        ],
+        &[("SEABOX_HOSTS", "[\"registry.internal:10.0.0.5\"]")],
+    );
+    assert!(output.status.success());
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--add-host", "seabox-hosts:127.0.0.1"));
+    assert!(contains_pair(
+        run,
+        "--add-host",
+        "registry.internal:10.0.0.5"
+    ));
+    assert!(contains_pair(
+        run,
+        "--add-host",
+        "artifacts.internal:fd00::5"
+    ));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--add-host",
+        "registry.internal",
+        "invalid",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn config_values_expand_environment_variables() {
+    let harness = Harness::new("interpolate");
+    let config_dir = harness.dir.join("config/seabox");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("seabox.toml"),
+        "image = \"${FAKE_REGISTRY}/library/fake:latest\"\nhostname = \"$FAKE_HOST-box\"\n",
+    )
+    .unwrap();
+
+    let output = harness.run_binary(
+        &["create", "interpolated"],
+        &[("FAKE_REGISTRY", "docker.io"), ("FAKE_HOST", "dev")],
+    );
+    assert!(output.status.success());
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(run.contains(&"docker.io/library/fake:latest".to_string()));
+    assert!(contains_pair(run, "--hostname", "dev-box"));
+
+    let output = harness.run_binary(&["create", "unset"], &[("FAKE_HOST", "dev")]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'FAKE_REGISTRY'"));
+}
+
+#[test]
+fn images_outside_allowed_prefixes_are_rejected_before_pulling() {
+    let harness = Harness::new("allowed_images");
+    let config_dir = harness.dir.join("config/seabox");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("seabox.toml"),
+        "allowed_image_prefixes = [\"registry.example.com/\"]\n",
+    )
+    .unwrap();
+
+    let output = harness.run_binary(
+        &["create", "-i", "docker.io/library/fake:latest", "hub"],
+        &[],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("allowed_image_prefixes"));
+    assert!(harness.commands().is_empty());
+
+    let output = harness.run_binary(
+        &[
+            "create",
+            "-i",
+            "registry.example.com/fake:latest",
+            "internal",
+        ],
+        &[],
+    );
+    assert!(output.status.success());
+
+    let output = harness.run_binary(
+        &[
+            "create",
+            "--allow-any-image",
+            "-i",
+            "docker.io/library/fake:latest",
+            "anyway",
+        ],
+        &[],
+    );
+    assert!(output.status.success());
+
+    // The flag is recorded, so the box can be recreated
+    let output = harness.run_binary(&["recreate", "anyway"], &[]);
+    assert!(output.status.success());
+    let output = harness.run_binary(&["update", "anyway"], &[]);
+    assert!(output.status.success());
+
+    // Without a record of the flag, update needs it too
+    std::fs::remove_file(harness.dir.join("data/seabox/boxes/anyway.lock.json")).unwrap();
+    let output = harness.run_binary(&["update", "anyway"], &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("allowed_image_prefixes"));
+    assert!(stderr.contains("--allow-any-image"));
+    let output = harness.run_binary(&["update", "--allow-any-image", "anyway"], &[]);
+    assert!(output.status.success());
+
+    // Base images of a Containerfile aren't checked, so building needs the flag too
+    let output = harness.run_binary(&["create", "--build", "ctx", "built"], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-any-image"));
+
+    let output = harness.run_binary(
+        &["create", "--build", "ctx", "--allow-any-image", "built"],
+        &[],
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn spec_file_sets_options_below_cli_flags() {
+    let harness = Harness::new("spec");
+    let spec = harness.dir.join("spec.json");
+    std::fs::write(
+        &spec,
+        r#"{"image": "docker.io/library/fake:latest", "ulimit": ["nofile=1024"], "hostname": "from-spec"}"#,
+    )
+    .unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "--spec",
+            spec.to_str().unwrap(),
+            "--hostname",
+            "from-cli",
+            "specified",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(run.contains(&"docker.io/library/fake:latest".to_string()));
+    assert!(contains_pair(run, "--ulimit", "nofile=1024"));
+    assert!(contains_pair(run, "--hostname", "from-cli"));
+
+    let spec = harness.dir.join("spec.toml");
+    std::fs::write(
+        &spec,
+        "image = \"docker.io/library/fake:latest\"\nimgae = \"typo\"\n",
+    )
+    .unwrap();
+
+    let result = harness.run(&["create", "--spec", spec.to_str().unwrap(), "unknown"]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(msg)) if msg.ends_with(": imgae")));
+}
+
+#[test]
+fn labels_from_cli_and_file_are_added() {
+    let harness = Harness::new("label_file");
+    let labels = harness.dir.join("labels");
+    std::fs::write(&labels, "# Owner\nteam=infra\n\n  tier=dev  \n").unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--label",
+            "project=seabox",
+            "--label-file",
+            labels.to_str().unwrap(),
+            "labelled",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--label", "seabox=true"));
+    assert!(contains_pair(run, "--label", "project=seabox"));
+    assert!(contains_pair(run, "--label", "team=infra"));
+    assert!(contains_pair(run, "--label", "tier=dev"));
+
+    std::fs::write(&labels, "team=infra\nmalformed\n").unwrap();
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--label-file",
+        labels.to_str().unwrap(),
+        "malformed",
+    ]);
+    assert!(
+        matches!(result, Err(SeaboxError::InvalidArgument(msg)) if msg.starts_with(&format!("{}:2:", labels.display())))
+    );
+}
+
+#[test]
+fn build_creates_container_from_built_image() {
+    let harness = Harness::new("build");
+
+    harness
+        .run(&[
+            "create",
+            "--build",
+            "ctx",
+            "--build-arg",
+            "VERSION=1.2",
+            "--build-target",
+            "dev",
+            "built",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    assert!(
+        commands.contains(
+            &[
+                "podman",
+                "build",
+                "-t",
+                "localhost/seabox-build-built",
+                "--build-arg",
+                "VERSION=1.2",
+                "--target",
+                "dev",
+                "ctx",
+            ]
+            .map(String::from)
+            .to_vec()
+        )
+    );
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(run.contains(&"localhost/seabox-build-built".to_string()));
+
+    let result = harness.run(&["create", "--build", "ctx", "--build-arg", "VERSION", "bad"]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+
+    // Build options without --build are rejected
+    let parsed = Cli::try_parse_from(["seabox", "create", "--build-arg", "VERSION=1.2", "dev"]);
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn explain_idmap_prints_generated_option_and_ranges() {
+    let harness = Harness::new("explain_idmap");
+    let env = [("SEABOX_HOST_UID", "1234"), ("SEABOX_HOST_GID", "5678")];
+
+    let output = harness.run_binary(&["explain-idmap", "--uid", "1001"], &env);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "idmap=uids=1234-1001-1#0-0-1;gids=5678-1000-1#0-0-1"
+    );
+    assert!(lines.contains(&"  1234-1001-1: host uid 1234 is container uid 1001"));
+    assert!(lines.contains(&"  5678-1000-1: host gid 5678 is container gid 1000"));
+
+    let output = harness.run_binary(&["explain-idmap", "--root"], &env);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("idmap=uids=0-0-2000;gids=0-0-2000\n"));
+    assert!(stdout.contains("  0-0-2000: host uids 0-1999 are container uids 0-1999\n"));
+
+    // Nothing is run for it
+    assert!(harness.commands().is_empty());
+}
+
+#[test]
+fn host_uid_and_gid_replace_idmap_host_ids() {
+    let harness = Harness::new("host_ids");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--host-uid",
+            "1500",
+            "--host-gid",
+            "1600",
+            "-v",
+            "/tmp:/data",
+            "mapped",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(run.iter().any(|x| {
+        x.starts_with("type=bind,source=/tmp,destination=/data")
+            && x.contains("idmap=uids=1500-1000-1#0-0-1;gids=1600-1000-1#0-0-1")
+    }));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--host-uid",
+        "4294967295",
+        "invalid",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn restart_wait_checks_health() {
+    let harness = Harness::new("restart_wait");
+
+    let inspect = |health: &str| {
+        format!(
+            r#"[{{"Id": "0a1b", "ImageName": "docker.io/library/fake:latest", "Mounts": [],
+                "State": {{"Running": true, "Health": {{"Status": "{health}"}}}},
+                "Config": {{"User": "1000:", "Labels": {{}}}}}}]"#
+        )
+    };
+    std::fs::write(harness.dir.join("container-ready.json"), inspect("healthy")).unwrap();
+    std::fs::write(
+        harness.dir.join("container-booting.json"),
+        inspect("starting"),
+    )
+    .unwrap();
+
+    harness.run(&["restart", "--wait", "ready"]).unwrap();
+    assert_eq!(
+        harness.commands().last().unwrap(),
+        &["podman", "container", "inspect", "ready"]
+    );
+
+    let result = harness.run(&["restart", "--wait", "--wait-timeout", "0", "booting"]);
+    assert!(matches!(result, Err(SeaboxError::CommandFailed(_))));
+}
+
+#[test]
+fn refuses_to_run_inside_a_box_without_force() {
+    let harness = Harness::new("nested");
+    let in_box = [("SEABOX_IN_BOX", "1")];
+
+    let output = harness.run_binary(&["enter", "dev"], &in_box);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Already inside a seabox container"));
+    assert!(harness.commands().is_empty());
+
+    let output = harness.run_binary(
+        &[
+            "create",
+            "--force",
+            "-i",
+            "docker.io/library/fake:latest",
+            "nested",
+        ],
+        &in_box,
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Warning: running inside"));
+
+    // Containers carry the marker, so seabox run in them notices
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--env", "SEABOX_IN_BOX=1"));
+}
+
+#[test]
+fn memory_limits_are_validated() {
+    let harness = Harness::new("memory");
+    let create = |extra: &[&str]| {
+        let mut args = vec!["create", "-i", "docker.io/library/fake:latest"];
+        args.extend(extra);
+        harness.run(&args)
+    };
+
+    create(&[
+        "--memory",
+        "4g",
+        "--memory-swap",
+        "6g",
+        "--memory-swappiness",
+        "10",
+        "limited",
+    ])
+    .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--memory", "4g"));
+    assert!(contains_pair(run, "--memory-swap", "6g"));
+    assert!(contains_pair(run, "--memory-swappiness", "10"));
+
+    create(&["--memory", "512m", "--memory-swap", "-1", "unlimited_swap"]).unwrap();
+
+    for invalid in [
+        &["--memory", "lots", "invalid"][..],
+        &["--memory", "4g", "--memory-swap", "2g", "invalid"],
+        &["--memory-swap", "4g", "invalid"],
+    ] {
+        assert!(matches!(
+            create(invalid),
+            Err(SeaboxError::InvalidArgument(_))
+        ));
+    }
+}
+
+#[test]
+fn cpu_limits_are_passed_and_cpuset_validated() {
+    let harness = Harness::new("cpu");
+    let create = |extra: &[&str]| {
+        let mut args = vec!["create", "-i", "docker.io/library/fake:latest"];
+        args.extend(extra);
+        harness.run(&args)
+    };
+
+    create(&[
+        "--cpus",
+        "1.5",
+        "--cpuset-cpus",
+        "0-3,6",
+        "--cpu-shares",
+        "512",
+        "pinned",
+    ])
+    .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--cpus", "1.5"));
+    assert!(contains_pair(run, "--cpuset-cpus", "0-3,6"));
+    assert!(contains_pair(run, "--cpu-shares", "512"));
+
+    for invalid in [
+        &["--cpus", "0", "invalid"][..],
+        &["--cpuset-cpus", "3-1", "invalid"],
+        &["--cpuset-cpus", "0,,2", "invalid"],
+        &["--cpuset-cpus", "all", "invalid"],
+    ] {
+        assert!(matches!(
+            create(invalid),
+            Err(SeaboxError::InvalidArgument(_))
+        ));
+    }
+}
+
+#[test]
+fn secrets_are_checked_and_passed() {
+    let harness = Harness::new("secret");
+    std::fs::write(harness.dir.join("secret-token"), "").unwrap();
+    let create = |secret: &str, name: &str| {
+        harness.run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--secret",
+            secret,
+            name,
+        ])
+    };
+
+    create("token,type=env,target=TOKEN", "with_secret").unwrap();
+
+    let commands = harness.commands();
+    let inspect = commands
+        .iter()
+        .position(|c| c[..3] == ["podman", "secret", "inspect"])
+        .unwrap();
+    assert_eq!(commands[inspect][3], "token");
+    let run = commands
+        .iter()
+        .position(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(inspect < run);
+    assert!(contains_pair(
+        &commands[run],
+        "--secret",
+        "token,type=env,target=TOKEN"
+    ));
+
+    let runs = |harness: &Harness| {
+        harness
+            .commands()
+            .iter()
+            .filter(|c| c[..3] == ["podman", "run", "--label"])
+            .count()
+    };
+    assert!(matches!(
+        create("missing", "without_secret"),
+        Err(SeaboxError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        create("token,type", "malformed"),
+        Err(SeaboxError::InvalidArgument(_))
+    ));
+    assert_eq!(runs(&harness), 1);
+}
+
+#[test]
+fn shell_rcfile_is_mounted_read_only() {
+    let harness = Harness::new("shell_rcfile");
+    let rcfile = harness.dir.join("bashrc");
+    std::fs::write(&rcfile, "alias ll='ls -l'\n").unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--shell-rcfile",
+            rcfile.to_str().unwrap(),
+            "rc",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    let source = std::fs::canonicalize(&rcfile).unwrap();
+    assert!(contains_pair(
+        run,
+        "--mount",
+        &format!(
+            "type=bind,source={},destination=/run/seabox/rcfile,ro=true",
+            source.display()
+        )
+    ));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--shell-rcfile",
+        "/nonexistent/bashrc",
+        "missing",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn custom_init_script_replaces_built_in_one() {
+    let harness = Harness::new("init_script");
+    let script = harness.dir.join("init.sh");
+    std::fs::write(
+        &script,
+        "echo custom INSERT_NEW_USERNAME INSERT_CONTAINER_ID INSERT_CREATE_USER INSERT_SETUP_ONLY\n",
+    )
+    .unwrap();
+
+    harness
+        .run(&[
+            "create",
+            "--init-script",
+            script.to_str().unwrap(),
+            "-i",
+            "docker.io/library/fake:latest",
+            "custom",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let setup = commands
+        .iter()
+        .find(|c| contains_pair(c, "--user", "root"))
+        .unwrap();
+    assert_eq!(setup.last().unwrap(), "echo custom user 1000  1\n");
+
+    std::fs::write(&script, "echo INSERT_NEW_USERNAME\n").unwrap();
+    let result = harness.run(&[
+        "create",
+        "--init-script",
+        script.to_str().unwrap(),
+        "-i",
+        "docker.io/library/fake:latest",
+        "incomplete",
+    ]);
+    assert!(
+        matches!(result, Err(SeaboxError::InvalidArgument(message)) if message.contains("INSERT_SETUP_ONLY"))
+    );
+
+    // Root boxes set up no user, so their init script isn't read
+    let root = ["--root", "--init-script", "/nonexistent/init.sh"];
+    let image = ["-i", "docker.io/library/fake:latest"];
+    harness
+        .run(&[&["create"][..], &root, &image, &["rooted"]].concat())
+        .unwrap();
+    harness
+        .run(&[&["tmp"][..], &root, &image].concat())
+        .unwrap();
+}
+
+#[test]
+fn verbose_reports_cli_overrides_of_profile() {
+    let harness = Harness::new("verbose_overrides");
+    let config_dir = harness.dir.join("config/seabox");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("seabox.toml"),
+        "[\"docker.io/library/fake:latest\"]\nno_password = true\nhostname = \"box\"\n",
+    )
+    .unwrap();
+
+    let output = harness.run_binary(
+        &[
+            "create",
+            "--verbose",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--no-password=false",
+            "--hostname",
+            "box",
+            "dev2",
+        ],
+        &[],
+    );
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Command line overrides no_password: configured true, effective false")
+    );
+    // Flags that agree with the profile aren't reported
+    assert!(!stderr.contains("overrides hostname"));
+}
+
+#[test]
+fn tz_sets_timezone_of_container() {
+    let harness = Harness::new("tz");
+    let run_of = |name: &str| {
+        harness
+            .commands()
+            .into_iter()
+            .find(|c| c[..3] == ["podman", "run", "--label"] && c.contains(&name.to_string()))
+            .unwrap()
+    };
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--tz",
+            "Europe/Berlin",
+            "berlin",
+        ])
+        .unwrap();
+    assert!(contains_pair(
+        &run_of("berlin"),
+        "--env",
+        "TZ=Europe/Berlin"
+    ));
+
+    // Hosts without /etc/localtime can't share it
+    if let Ok(localtime) = std::fs::canonicalize("/etc/localtime") {
+        harness
+            .run(&[
+                "create",
+                "-i",
+                "docker.io/library/fake:latest",
+                "--tz",
+                "host",
+                "local",
+            ])
+            .unwrap();
+        assert!(contains_pair(
+            &run_of("local"),
+            "--mount",
+            &format!(
+                "type=bind,source={},destination=/etc/localtime,ro=true",
+                localtime.display()
+            )
+        ));
+    }
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--tz",
+        "",
+        "invalid",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn resolv_conf_is_mounted_unless_host_networking() {
+    let harness = Harness::new("resolv_conf");
+    let resolv_conf = harness.dir.join("resolv.conf");
+    std::fs::write(&resolv_conf, "nameserver 10.0.0.53\n").unwrap();
+    let resolv_conf = std::fs::canonicalize(&resolv_conf).unwrap();
+    let mount = format!(
+        "type=bind,source={},destination=/etc/resolv.conf,ro=true",
+        resolv_conf.display()
+    );
+    let run_of = |name: &str| {
+        harness
+            .commands()
+            .into_iter()
+            .find(|c| c[..3] == ["podman", "run", "--label"] && c.contains(&name.to_string()))
+            .unwrap()
+    };
+
+    for (name, network) in [("bridged", "bridge"), ("hosted", "host")] {
+        harness
+            .run(&[
+                "create",
+                "-i",
+                "docker.io/library/fake:latest",
+                "--network",
+                network,
+                "--resolv-conf",
+                resolv_conf.to_str().unwrap(),
+                name,
+            ])
+            .unwrap();
+    }
+    assert!(contains_pair(&run_of("bridged"), "--mount", &mount));
+    assert!(
+        !run_of("hosted")
+            .iter()
+            .any(|x| x.contains("/etc/resolv.conf"))
+    );
+
+    // An explicit file wins over copying the host's
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--network",
+            "bridge",
+            "--copy-resolv-conf",
+            "--resolv-conf",
+            resolv_conf.to_str().unwrap(),
+            "both",
+        ])
+        .unwrap();
+    assert!(contains_pair(&run_of("both"), "--mount", &mount));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--network",
+        "bridge",
+        "--resolv-conf",
+        "/nonexistent/resolv.conf",
+        "missing",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn pid_namespace_joins_only_existing_boxes() {
+    let harness = Harness::new("pid");
+    std::fs::write(
+        harness.dir.join("container-foreign.json"),
+        r#"[{"Id": "5e6f", "ImageName": "docker.io/library/fake:latest", "Mounts": [],
+            "State": {"Running": true}, "Config": {"User": "", "Labels": {}}}]"#,
+    )
+    .unwrap();
+    let create = |pid: &str| {
+        harness.run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--pid",
+            pid,
+            "debug",
+        ])
+    };
+
+    create("container:dev").unwrap();
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--pid", "container:dev"));
+
+    assert!(matches!(
+        create("container:missing"),
+        Err(SeaboxError::ContainerNotFound { name, .. }) if name == "missing"
+    ));
+    assert!(matches!(
+        create("container:foreign"),
+        Err(SeaboxError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        create("shared"),
+        Err(SeaboxError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn enter_gives_up_if_started_container_never_runs() {
+    let harness = Harness::new("enter_start_timeout");
+    std::fs::write(
+        harness.dir.join("container-stuck.json"),
+        r#"[{"Id": "7a8b", "ImageName": "docker.io/library/fake:latest", "Mounts": [],
+            "State": {"Running": false}, "Config": {"User": "1000:", "Labels": {"seabox": "true"}}}]"#,
+    )
+    .unwrap();
+
+    let output = harness.run_binary(&["enter", "stuck"], &[("SEABOX_START_TIMEOUT", "0")]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Starting stopped box 'stuck'..."));
+    assert!(stderr.contains("isn't running after 0s"));
+
+    // Started, then inspected again rather than exec'd into
+    let commands = harness.commands();
+    assert!(commands.contains(&["podman", "start", "stuck"].map(String::from).to_vec()));
+    assert!(commands.iter().all(|c| c[1] != "exec"));
+
+    // --no-start fails without starting it
+    std::fs::remove_file(harness.log()).unwrap();
+    let output = harness.run_binary(&["enter", "--no-start", "stuck"], &[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Container 'stuck' isn't running"));
+    assert!(harness.commands().iter().all(|c| c[1] != "start"));
+}
+
+#[test]
+fn workdir_defaults_to_first_volume_without_directory() {
+    let harness = Harness::new("workdir");
+    let run_of = |name: &str| {
+        harness
+            .commands()
+            .into_iter()
+            .find(|c| c[..3] == ["podman", "run", "--label"] && c.contains(&name.to_string()))
+            .unwrap()
+    };
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-v",
+            "/tmp:/work",
+            "volumed",
+        ])
+        .unwrap();
+    assert!(contains_pair(&run_of("volumed"), "-w", "/work"));
+
+    // Volumes that don't come from the command line keep the image's directory
+    let env = [("SEABOX_VOLUMES", "/tmp:/shared")];
+    let output = harness.run_binary(
+        &["create", "-i", "docker.io/library/fake:latest", "shared"],
+        &env,
+    );
+    assert!(output.status.success());
+    let run = run_of("shared");
+    assert!(run.iter().any(|x| x.contains("destination=/shared")));
+    assert!(!run.contains(&"-w".to_string()));
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-d",
+            "/tmp",
+            "-w",
+            "/code",
+            "explicit",
+        ])
+        .unwrap();
+    let run = run_of("explicit");
+    assert_eq!(run.iter().filter(|x| *x == "-w").count(), 1);
+    assert!(contains_pair(&run, "-w", "/code"));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "-w",
+        "code",
+        "relative",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn group_add_accepts_names_and_gids() {
+    let harness = Harness::new("group_add");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--group-add",
+            "docker",
+            "--group-add",
+            "988",
+            "grouped",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(contains_pair(run, "--group-add", "docker"));
+    assert!(contains_pair(run, "--group-add", "988"));
+
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--group-add",
+        "bad group",
+        "invalid",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn mount_cache_creates_missing_volume_once() {
+    let harness = Harness::new("mount_cache");
+
+    for name in ["rust1", "rust2"] {
+        harness
+            .run(&[
+                "create",
+                "-i",
+                "docker.io/library/fake:latest",
+                "--mount-cache",
+                "cargo:/home/user/.cargo",
+                name,
+            ])
+            .unwrap();
+    }
+
+    let commands = harness.commands();
+    let creates: Vec<_> = commands
+        .iter()
+        .filter(|c| c[..3] == ["podman", "volume", "create"])
+        .collect();
+    assert_eq!(creates.len(), 1);
+    assert!(contains_pair(creates[0], "--label", "SEABOX_CACHE=true"));
+    assert_eq!(creates[0].last().unwrap(), "seabox-cache-cargo");
+
+    let runs: Vec<_> = commands
+        .iter()
+        .filter(|c| c[..3] == ["podman", "run", "--label"])
+        .collect();
+    assert_eq!(runs.len(), 2);
+    for run in runs {
+        assert!(contains_pair(
+            run,
+            "-v",
+            "seabox-cache-cargo:/home/user/.cargo:U"
+        ));
+    }
+
+    // Nothing is created for a box that already exists
+    let result = harness.run(&[
+        "create",
+        "-i",
+        "docker.io/library/fake:latest",
+        "--mount-cache",
+        "npm:/home/user/.npm",
+        "dev",
+    ]);
+    assert!(matches!(result, Err(SeaboxError::ContainerExists(_))));
+    assert!(
+        harness
+            .commands()
+            .iter()
+            .all(|c| c.last().unwrap() != "seabox-cache-npm")
+    );
+}
+
+#[test]
+fn pass_through_end_comes_after_generated_flags() {
+    let harness = Harness::new("pass_through_end");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "-p",
+            "--cidfile /tmp/start",
+            "--pass-through-end",
+            "--network bridge",
+            "ordered",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+
+    let position = |flag: &str, value: &str| {
+        run.windows(2)
+            .rposition(|w| w[0] == flag && w[1] == value)
+            .unwrap()
+    };
+    let start = position("--cidfile", "/tmp/start");
+    let generated = position("--network", "host");
+    let end = position("--network", "bridge");
+    let name = position("--name", "ordered");
+
+    assert!(start < generated);
+    assert!(generated < end);
+    assert!(end < name);
+}
+
+#[test]
+fn create_records_engine_mode_and_remove_forgets_it() {
+    let harness = Harness::new("metadata");
+    let metadata = harness.data_dir().join("boxes/recorded.json");
+
+    harness
+        .run(&["create", "-i", "docker.io/library/fake:latest", "recorded"])
+        .unwrap();
+
+    let recorded: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&metadata).unwrap()).unwrap();
+    assert_eq!(recorded["name"], "recorded");
+    assert_eq!(recorded["image"], "docker.io/library/fake:latest");
+    assert_eq!(recorded["rootful"], true);
+
+    harness.run(&["rm", "recorded"]).unwrap();
+    assert!(!metadata.exists());
+}
+
+#[test]
+fn prune_expired_removes_boxes_past_rm_after() {
+    let harness = Harness::new("rm_after");
+    let boxes = harness.data_dir().join("boxes");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--rm-after",
+            "2h",
+            "fresh",
+        ])
+        .unwrap();
+
+    let recorded: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(boxes.join("fresh.json")).unwrap()).unwrap();
+    assert!(recorded["expires"].as_u64().is_some());
+
+    std::fs::write(
+        boxes.join("stale.json"),
+        r#"{"name": "stale", "image": "docker.io/library/fake:latest", "rootful": true, "expires": 1}"#,
+    )
+    .unwrap();
+
+    std::fs::remove_file(harness.log()).unwrap();
+    harness.run(&["prune", "--expired"]).unwrap();
+
+    let commands = harness.commands();
+    assert!(
+        commands
+            .iter()
+            .any(|c| c[..3] == ["podman", "container", "rm"] && c.last().unwrap() == "stale")
+    );
+    assert!(commands.iter().all(|c| c.last().unwrap() != "fresh"));
+    assert!(!boxes.join("stale.json").exists());
+    assert!(boxes.join("fresh.json").exists());
+
+    let result = harness.run(&["create", "--rm-after", "2 hours", "invalid"]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn recreate_uses_recorded_config_and_arguments() {
+    let harness = Harness::new("recreate");
+    let lock = harness.data_dir().join("boxes/locked.lock.json");
+
+    harness
+        .run(&[
+            "create",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--ulimit",
+            "nofile=1024:2048",
+            "locked",
+        ])
+        .unwrap();
+
+    let recorded: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&lock).unwrap()).unwrap();
+    assert_eq!(recorded["args"]["image"], "docker.io/library/fake:latest");
+    assert_eq!(recorded["config"]["ulimit"][0], "nofile=1024:2048");
+
+    let created = harness.commands().len();
+    harness.run(&["recreate", "locked"]).unwrap();
+
+    let commands = harness.commands();
+    let recreated = &commands[created..];
+    assert!(
+        recreated
+            .iter()
+            .any(|c| c[..3] == ["podman", "container", "rm"])
+    );
+
+    let run = recreated
+        .iter()
+        .find(|c| c[..2] == ["podman", "run"] && c.contains(&"--name".to_string()))
+        .unwrap();
+    assert!(contains_pair(run, "--ulimit", "nofile=1024:2048"));
+    assert!(run.contains(&"docker.io/library/fake:latest".to_string()));
+
+    harness.run(&["rm", "locked"]).unwrap();
+    assert!(!lock.exists());
+
+    let result = harness.run(&["recreate", "locked"]);
+    assert!(matches!(result, Err(SeaboxError::Metadata(_))));
+}
+
+#[test]
+fn enter_replaces_box_whose_image_changed() {
+    let harness = Harness::new("replace_on_image_change");
+    let metadata = harness.data_dir().join("boxes/moving.json");
+
+    harness
+        .run(&["create", "-i", "docker.io/library/fake:latest", "moving"])
+        .unwrap();
+
+    let recorded: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&metadata).unwrap()).unwrap();
+    assert_eq!(
+        recorded["image_id"],
+        "9e8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b3a2f1e0d9c8b7a6f5e4d3c2b1a0f9e8d"
+    );
+
+    // An unchanged image leaves the box alone
+    std::fs::remove_file(harness.log()).unwrap();
+    harness
+        .run(&["enter", "--replace-on-image-change", "moving"])
+        .unwrap();
+    assert!(
+        harness
+            .commands()
+            .iter()
+            .all(|c| c[..3] != ["podman", "container", "rm"])
+    );
+
+    let mut stale = recorded.clone();
+    stale["image_id"] = "0ld1d".into();
+    std::fs::write(&metadata, stale.to_string()).unwrap();
+
+    std::fs::remove_file(harness.log()).unwrap();
+    harness
+        .run(&[
+            "enter",
+            "--replace-on-image-change",
+            "--yes",
+            "--detach-keys",
+            "ctrl-x",
+            "moving",
+        ])
+        .unwrap();
+
+    let commands = harness.commands();
+    let removed = commands
+        .iter()
+        .position(|c| c[..3] == ["podman", "container", "rm"])
+        .unwrap();
+    let created = commands
+        .iter()
+        .position(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    assert!(removed < created);
+    assert!(contains_pair(commands.last().unwrap(), "--user", "1000:"));
+    // The box is entered with the options of enter, not those it was created with
+    assert!(
+        commands
+            .last()
+            .unwrap()
+            .contains(&"--detach-keys=ctrl-x".to_string())
+    );
+
+    let recorded: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&metadata).unwrap()).unwrap();
+    assert_ne!(recorded["image_id"], "0ld1d");
+}
+
+#[test]
+fn dry_run_diff_compares_with_existing_box() {
+    let harness = Harness::new("dry_run_diff");
+
+    harness
+        .run(&["create", "-i", "docker.io/library/fake:latest", "compared"])
+        .unwrap();
+
+    let output = harness.run_binary(
+        &[
+            "create",
+            "--dry-run",
+            "--diff",
+            "-i",
+            "docker.io/library/other:latest",
+            "compared",
+        ],
+        &[],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Changes to 'compared':"));
+    assert!(
+        stdout.contains(
+            r#"image: "docker.io/library/fake:latest" -> "docker.io/library/other:latest""#
+        )
+    );
+
+    // Boxes created before their configuration was recorded are reconstructed from the container
+    let output = harness.run_binary(
+        &[
+            "create",
+            "--dry-run",
+            "--diff",
+            "-i",
+            "docker.io/library/fake:latest",
+            "dev",
+        ],
+        &[],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Changes to 'dev':"));
+    assert!(stdout.contains("directory: \"/nonexistent/project\""));
+
+    // The reconstruction goes through the same preset, so it doesn't show up as a change
+    let config_dir = harness.dir.join("config/seabox");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("seabox.toml"),
+        "[presets.net]\nnetwork = \"bridge\"\n",
+    )
+    .unwrap();
+    let output = harness.run_binary(
+        &[
+            "create",
+            "--dry-run",
+            "--diff",
+            "--preset",
+            "net",
+            "-i",
+            "docker.io/library/fake:latest",
+            "dev",
+        ],
+        &[],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let changes = stdout.split("Changes to 'dev':\n").nth(1).unwrap();
+    assert!(changes.starts_with("  directory: "));
+    assert!(!changes.contains("  network: "));
+
+    let result = Cli::try_parse_from(["seabox", "create", "--diff", "dev"]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn update_keeps_recovered_volumes_without_adding_env_volumes() {
+    let harness = Harness::new("update_volumes");
+    std::fs::write(
+        harness.dir.join("container-shared.json"),
+        r#"[{"Id": "3c4d", "ImageName": "docker.io/library/fake:latest",
+            "Mounts": [{"Type": "bind", "Source": "/tmp", "Destination": "/shared"}],
+            "State": {"Running": true}, "Config": {"User": "1000:",
+            "Labels": {"seabox": "true", "SEABOX_RESOLVED_UID": "1000", "SEABOX_RESOLVED_GID": "1000"}}}]"#,
+    )
+    .unwrap();
+
+    // The container's volumes already include those of SEABOX_VOLUMES when it was created
+    let output = harness.run_binary(&["update", "shared"], &[("SEABOX_VOLUMES", "/tmp:/shared")]);
+    assert!(output.status.success());
+
+    let commands = harness.commands();
+    let run = commands
+        .iter()
+        .find(|c| c[..3] == ["podman", "run", "--label"])
+        .unwrap();
+    let shared = run
+        .iter()
+        .filter(|x| x.contains("destination=/shared"))
+        .count();
+    assert_eq!(shared, 1);
+}
+
+#[test]
+fn remove_image_skips_images_still_in_use() {
+    let harness = Harness::new("remove_image");
+    let image = "docker.io/library/fake:latest";
+
+    harness.run(&["create", "-i", image, "throwaway"]).unwrap();
+    harness.run(&["rm", "--remove-image", "throwaway"]).unwrap();
+    assert!(
+        harness
+            .commands()
+            .iter()
+            .any(|c| *c == ["podman", "rmi", image])
+    );
+
+    std::fs::remove_file(harness.log()).unwrap();
+    std::fs::write(harness.dir.join("image-in-use"), "").unwrap();
+
+    harness.run(&["create", "-i", image, "shared"]).unwrap();
+    harness.run(&["rm", "--remove-image", "shared"]).unwrap();
+
+    let commands = harness.commands();
+    assert!(contains_pair(
+        commands.last().unwrap(),
+        "--filter",
+        &format!("ancestor={image}")
+    ));
+    assert!(commands.iter().all(|c| c[..2] != ["podman", "rmi"]));
+}
+
+#[test]
+fn enter_uses_engine_mode_the_box_was_created_in() {
+    let harness = Harness::new("rootless_enter");
+    // The binary keeps its data in a seabox directory of XDG_DATA_HOME
+    let boxes = harness.data_dir().join("seabox/boxes");
+    std::fs::create_dir_all(&boxes).unwrap();
+    std::fs::write(
+        boxes.join("dev.json"),
+        r#"{"name": "dev", "image": "docker.io/library/fake:latest", "rootful": false}"#,
+    )
+    .unwrap();
+
+    // Rootless podman is run directly rather than through the (fake) sudo command
+    let output = harness.run_binary(&["enter", "dev"], &[("PATH", &harness.rootless_path())]);
+    assert!(output.status.success());
+
+    let rootless = std::fs::read_to_string(harness.dir.join("rootless.log")).unwrap();
+    assert!(rootless.lines().any(|x| x == "container inspect"));
+    assert!(rootless.lines().any(|x| x == "exec -it"));
+    // Every invocation was rootless, none went through the sudo command
+    assert_eq!(rootless.lines().count(), harness.commands().len());
+
+    let result = harness.run(&["enter", "../dev"]);
+    assert!(matches!(result, Err(SeaboxError::InvalidArgument(_))));
+}
+
+#[test]
+fn dry_run_reports_missing_image_without_pulling() {
+    let harness = Harness::new("dry_run_missing_image");
+    std::fs::write(harness.dir.join("no-image"), "").unwrap();
+
+    let output = harness.run_binary(
+        &[
+            "create",
+            "--dry-run",
+            "-i",
+            "docker.io/library/fake:latest",
+            "dev",
+        ],
+        &[],
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("podman pull docker.io/library/fake:latest"));
+    assert!(stdout.contains("podman run --label 'seabox=true'"));
+    assert!(stderr.contains("is not present locally and would be pulled"));
+    assert!(stderr.contains("uid 1000 and gid 1000, created by seabox"));
+
+    // Only the image was inspected - nothing was pulled, probed or created
+    assert!(
+        harness
+            .commands()
+            .iter()
+            .all(|c| c[..2] == ["podman", "image"])
+    );
+}
+
+#[test]
+fn dry_run_prints_json_argv() {
+    let harness = Harness::new("dry_run_json");
+
+    let output = harness.run_binary(
+        &[
+            "create",
+            "--dry-run",
+            "--output",
+            "json",
+            "-i",
+            "docker.io/library/fake:latest",
+            "--label",
+            "note=hello world",
+            "json",
+        ],
+        &[],
+    );
+    assert!(output.status.success());
+
+    // Every line of stdout is a command, with arguments exactly as they would be passed
+    let commands: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let create = commands.iter().find(|c| c["purpose"] == "create").unwrap();
+    let argv: Vec<&str> = create["argv"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| x.as_str().unwrap())
+        .collect();
+    assert_eq!(argv[1..3], ["podman", "run"]);
+    assert!(argv.contains(&"seabox=true"));
+    assert!(argv.contains(&"note=hello world"));
+}
+
+#[test]
+fn create_fails_if_container_exists() {
+    let harness = Harness::new("create_exists");
+
+    let result = harness.run(&["create", "-i", "docker.io/library/fake:latest", "dev"]);
+
+    assert!(matches!(result, Err(SeaboxError::ContainerExists(name)) if name == "dev"));
+    assert!(
+        harness
+            .commands()
+            .iter()
+            .all(|c| c[..2] != ["podman", "run"] || c.contains(&"--rm".to_string()))
+    );
+}
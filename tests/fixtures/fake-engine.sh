@@ -0,0 +1,137 @@
+#!/bin/sh
+# Stand-in for `sudo podman` in the integration tests, configured as the sudo command.
+# Records its arguments to $SEABOX_FAKE_LOG and answers queries from the fixtures next to it.
+#
+# Arguments are separated by \037 (unit separator), invocations by \036 (record separator).
+
+fixtures=$(dirname "$0")
+state=$(dirname "$SEABOX_FAKE_LOG")
+
+printf '%s\037' "$@" >>"$SEABOX_FAKE_LOG"
+printf '\036' >>"$SEABOX_FAKE_LOG"
+
+# Record engine environment set by --engine-env
+if [ -n "$CONTAINERS_STORAGE_CONF" ]; then
+    echo "CONTAINERS_STORAGE_CONF=$CONTAINERS_STORAGE_CONF" >>"$state/engine-env"
+fi
+
+# Drop "podman"
+shift
+
+case "$1 $2" in
+"container inspect")
+    if [ -f "$state/container-$3.json" ]; then
+        cat "$state/container-$3.json"
+    elif [ -f "$fixtures/container-$3.json" ]; then
+        cat "$fixtures/container-$3.json"
+    elif [ -f "$state/created-$3" ]; then
+        cat "$fixtures/container-created.json"
+    else
+        echo "Error: no such container $3" >&2
+        exit 125
+    fi
+    ;;
+"container rm")
+    for arg in "$@"; do
+        name="$arg"
+    done
+    if [ -f "$state/rm-exit-code" ]; then
+        exit "$(cat "$state/rm-exit-code")"
+    fi
+    rm -f "$state/created-$name" "$state/container-$name.json"
+    ;;
+"exec "*)
+    case "$*" in
+    *" cat /etc/passwd") cat "$fixtures/passwd" ;;
+    esac
+
+    # Simulate the init script or shell failing
+    if [ -f "$state/exec-exit-code" ]; then
+        exit "$(cat "$state/exec-exit-code")"
+    fi
+    ;;
+"image inspect")
+    if [ -f "$state/no-image" ]; then
+        echo "Error: $3: image not known" >&2
+        exit 125
+    fi
+    cat "$fixtures/image.json"
+    ;;
+"version "*)
+    echo "5.0.0"
+    ;;
+"secret inspect")
+    if [ ! -f "$state/secret-$3" ]; then
+        echo "Error: no secret with name or id \"$3\": no such secret" >&2
+        exit 125
+    fi
+    ;;
+"volume exists")
+    [ -f "$state/volume-$3" ] || exit 1
+    ;;
+"volume create")
+    for arg in "$@"; do
+        volume="$arg"
+    done
+    touch "$state/volume-$volume"
+    ;;
+"ps "*)
+    # Older podman versions print null rather than an empty array
+    if [ -f "$state/no-containers" ]; then
+        case "$*" in
+        *"--format json"*) echo null ;;
+        *--format*) ;;
+        *) head -n 1 "$fixtures/ps.txt" ;;
+        esac
+        exit 0
+    fi
+
+    case "$*" in
+    *ancestor=*) [ -f "$state/image-in-use" ] && echo "other" ;;
+    *"--format json"*)
+        # Rootless podman, run through a podman shim on PATH, keeps a separate store
+        if [ -n "$SEABOX_FAKE_ROOTLESS" ]; then
+            echo '[{"Id":"9c0e5b","Names":["scratch"],"Image":"docker.io/library/fake:latest","State":"exited"}]'
+        else
+            echo '[{"Id":"4f1ad1","Names":["dev"],"Image":"docker.io/library/fake:latest","State":"running"}]'
+        fi
+        ;;
+    *--format*) awk 'NR > 1 { print $NF }' "$fixtures/ps.txt" ;;
+    *) cat "$fixtures/ps.txt" ;;
+    esac
+    ;;
+"run "*)
+    # Simulate a kernel without idmapped mount support
+    if [ -f "$state/no-idmap-support" ] && case "$*" in *idmap=*) true ;; *) false ;; esac; then
+        echo "Error: crun: mount_setattr: idmap mounts not supported: Invalid argument" >&2
+        exit 126
+    fi
+
+    previous=""
+    for arg in "$@"; do
+        if [ "$previous" = "--runtime" ] && [ "$arg" = "missing-runtime" ]; then
+            echo "Error: default OCI runtime \"$arg\" not found: invalid argument" >&2
+            exit 125
+        fi
+        previous="$arg"
+    done
+
+    previous=""
+    for arg in "$@"; do
+        if [ "$previous" = "--name" ] && [ -n "$arg" ]; then
+            touch "$state/created-$arg"
+        fi
+        if [ "$previous" = "--entrypoint" ] && [ "$arg" = "cat" ]; then
+            cat "$fixtures/passwd"
+        fi
+        previous="$arg"
+    done
+
+    # Simulate the container command failing
+    if [ -f "$state/run-exit-code" ]; then
+        exit "$(cat "$state/run-exit-code")"
+    fi
+    ;;
+esac
+
+exit 0